@@ -12,11 +12,8 @@ struct CoinPrice {
 
 fn main() {
     // read coin id from stdin
-    let mut buf = [0; 1024];
-    let len = read_stdin(&mut buf).unwrap();
-    let coin_id = std::str::from_utf8(&buf[..len as usize])
-        .unwrap_or_default()
-        .trim();
+    let input = read_stdin_string().unwrap_or_default();
+    let coin_id = input.trim();
 
     // perform http request
     let http_opts = HttpOptions::new("GET", 30, 10);
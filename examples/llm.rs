@@ -9,7 +9,6 @@ use blockless_sdk::*;
 /// Each model is configured with a system message that changes the assistant's name.
 /// The example then sends chat requests to both models and prints their responses,
 /// demonstrating how the same instance maintains state between requests.
-
 fn main() {
     // large model
     let mut llm = BlocklessLlm::new("Llama-3.1-8B-Instruct-q4f32_1-MLC").unwrap();
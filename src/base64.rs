@@ -0,0 +1,97 @@
+//! Standard alphabet base64 with padding, for carrying binary payloads in JSON.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// `None` on characters outside the alphabet or anything but padding after
+/// the first `=`; whitespace is skipped.
+pub(crate) fn decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut bytes = text.bytes();
+    for c in bytes.by_ref() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            _ => return None,
+        };
+        acc = acc << 6 | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    bytes
+        .all(|c| c == b'=' || c.is_ascii_whitespace())
+        .then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        for data in [
+            &b""[..],
+            b"f",
+            b"fo",
+            b"foo",
+            b"foob",
+            b"fooba",
+            b"foobar",
+            &[0, 0xff, 0x10, 0x80],
+        ] {
+            assert_eq!(decode(&encode(data)).as_deref(), Some(data));
+        }
+        assert_eq!(encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(encode(b"fo"), "Zm8=");
+        assert_eq!(encode(b"f"), "Zg==");
+    }
+
+    #[test]
+    fn skips_whitespace_and_accepts_missing_padding() {
+        assert_eq!(decode("Zm9v\nYmFy\n").as_deref(), Some(&b"foobar"[..]));
+        assert_eq!(decode("Zm8").as_deref(), Some(&b"fo"[..]));
+        assert_eq!(decode("Zg== \n").as_deref(), Some(&b"f"[..]));
+    }
+
+    #[test]
+    fn rejects_data_after_padding() {
+        assert_eq!(decode("Zg==Zg=="), None);
+        assert_eq!(decode("Zm8=x"), None);
+        assert_eq!(decode("Zg=!"), None);
+    }
+
+    #[test]
+    fn rejects_characters_outside_the_alphabet() {
+        assert_eq!(decode("Zm9v-"), None);
+        assert_eq!(decode("Zm9v_"), None);
+    }
+}
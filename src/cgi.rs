@@ -1,8 +1,11 @@
-use std::fmt::{Debug, Display};
+use std::{
+    fmt::{Debug, Display},
+    time::{Duration, Instant},
+};
 
 use json::{object::Object, JsonValue};
 
-use crate::{cgi_host::*, CGIErrorKind};
+use crate::{base64, cgi_host::*, memory::read_env_vars_to_end, CGIErrorKind};
 
 #[derive(Debug)]
 pub struct CGIExtensions {
@@ -26,73 +29,293 @@ pub struct CGIEnv {
     pub value: String,
 }
 
+/// How stdin and the collected stdout/stderr cross the host boundary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CGITransport {
+    #[default]
+    Raw,
+    /// For hosts that pass extension I/O as text: everything written to stdin
+    /// is base64 encoded as one stream, padded when the output is collected,
+    /// and output is decoded once collected. Chunk-level reads see the encoded
+    /// bytes.
+    Base64,
+}
+
+impl CGITransport {
+    fn decode(self, data: Vec<u8>) -> Result<Vec<u8>, CGIErrorKind> {
+        match self {
+            CGITransport::Raw => Ok(data),
+            CGITransport::Base64 => std::str::from_utf8(&data)
+                .ok()
+                .and_then(base64::decode)
+                .ok_or(CGIErrorKind::EncodingError),
+        }
+    }
+}
+
 pub struct CGICommand {
     command: String,
     args: Vec<String>,
     envs: Vec<CGIEnv>,
+    timeout: Option<Duration>,
+    transport: CGITransport,
+    stdin: StdinEncoder,
     handle: Option<u32>,
 }
 
+/// Encodes stdin for the transport across writes. Base64 holds back the last
+/// partial 3-byte group until the next write, so padding only appears once,
+/// at the end of the stream.
+#[derive(Debug, Default)]
+struct StdinEncoder {
+    pending: Vec<u8>,
+    finished: bool,
+}
+
+impl StdinEncoder {
+    fn encode<'a>(
+        &mut self,
+        transport: CGITransport,
+        data: &'a [u8],
+    ) -> Result<std::borrow::Cow<'a, [u8]>, CGIErrorKind> {
+        match transport {
+            CGITransport::Raw => Ok(data.into()),
+            // anything after the padding would make the stream undecodable
+            CGITransport::Base64 if self.finished => Err(CGIErrorKind::WriteError),
+            CGITransport::Base64 => {
+                self.pending.extend_from_slice(data);
+                let whole = self.pending.len() / 3 * 3;
+                let encoded = base64::encode(&self.pending[..whole]);
+                self.pending.drain(..whole);
+                Ok(encoded.into_bytes().into())
+            }
+        }
+    }
+
+    /// The end of the stream: the held back bytes, padded.
+    fn finish(&mut self, transport: CGITransport) -> Vec<u8> {
+        if transport != CGITransport::Base64 || std::mem::replace(&mut self.finished, true) {
+            return Vec::new();
+        }
+        base64::encode(&std::mem::take(&mut self.pending)).into_bytes()
+    }
+}
+
 type ReadFn = unsafe extern "C" fn(u32, *mut u8, u32, *mut u32) -> u32;
 
+fn read_chunk(
+    handle: u32,
+    name: &str,
+    read_call: ReadFn,
+    buf: &mut [u8],
+) -> Result<u32, CGIErrorKind> {
+    let mut readn = 0u32;
+    let rs = host_call!(
+        "blockless_cgi",
+        name,
+        crate::ffi_debug::written(buf, readn),
+        unsafe { read_call(handle, buf.as_mut_ptr(), buf.len() as _, &mut readn) }
+    );
+    if rs != 0 {
+        return Err(CGIErrorKind::ReadError);
+    }
+    Ok(readn)
+}
+
+fn read_to_end(handle: u32, name: &str, read_call: ReadFn) -> Result<Vec<u8>, CGIErrorKind> {
+    let mut data: Vec<u8> = Vec::new();
+    let mut bs = [0u8; 1024];
+    loop {
+        match read_chunk(handle, name, read_call, &mut bs)? {
+            0 => break,
+            readn => data.extend_from_slice(&bs[..readn as _]),
+        }
+    }
+    Ok(data)
+}
+
+/// Reads stdout and stderr a chunk at a time in turn until the process closes
+/// both.
+///
+/// Host reads block and there is no way to ask which pipe has data, so this
+/// can still deadlock: a child that fills the stderr pipe while writing
+/// nothing to stdout blocks on its next stderr write while we block reading
+/// stdout. Only the timeout the host got when the command started ends that,
+/// by killing the process. `deadline` is checked between chunks and cannot
+/// interrupt a blocked read.
+fn read_output(handle: u32, deadline: Option<Instant>) -> Result<(Vec<u8>, Vec<u8>), CGIErrorKind> {
+    let mut streams: [(&str, ReadFn, Vec<u8>, bool); 2] = [
+        ("cgi_stdout_read", cgi_stdout_read, Vec::new(), true),
+        ("cgi_stderr_read", cgi_stderr_read, Vec::new(), true),
+    ];
+    let mut bs = [0u8; 1024];
+    while streams.iter().any(|(.., open)| *open) {
+        for (name, read_call, data, open) in streams.iter_mut().filter(|(.., open)| *open) {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(CGIErrorKind::TimedOut);
+            }
+            match read_chunk(handle, name, *read_call, &mut bs)? {
+                0 => *open = false,
+                readn => data.extend_from_slice(&bs[..readn as _]),
+            }
+        }
+    }
+    let [(.., stdout, _), (.., stderr, _)] = streams;
+    Ok((stdout, stderr))
+}
+
+fn write_chunk(handle: u32, buf: &[u8]) -> Result<u32, CGIErrorKind> {
+    let mut num = 0u32;
+    let rs = host_call!("blockless_cgi", "cgi_stdin_write", buf, unsafe {
+        cgi_stdin_write(handle, buf.as_ptr(), buf.len() as _, &mut num)
+    });
+    if rs != 0 {
+        return Err(CGIErrorKind::WriteError);
+    }
+    Ok(num)
+}
+
+fn write_all(handle: u32, mut buf: &[u8]) -> Result<(), CGIErrorKind> {
+    while !buf.is_empty() {
+        match write_chunk(handle, buf)? {
+            0 => return Err(CGIErrorKind::WriteError),
+            num => buf = &buf[num as usize..],
+        }
+    }
+    Ok(())
+}
+
+/// `write_stdin` for either transport. Encoded bytes cannot be partially
+/// accepted in a meaningful way, so with base64 the whole of `buf` is written.
+fn write_stdin(
+    handle: u32,
+    transport: CGITransport,
+    stdin: &mut StdinEncoder,
+    buf: &[u8],
+) -> Result<u32, CGIErrorKind> {
+    match transport {
+        CGITransport::Raw => write_chunk(handle, buf),
+        CGITransport::Base64 => {
+            write_all(handle, &stdin.encode(transport, buf)?)?;
+            Ok(buf.len() as u32)
+        }
+    }
+}
+
+#[cfg(feature = "cgi-status")]
+fn exit_status(handle: u32) -> Result<i32, CGIErrorKind> {
+    let mut status = 0i32;
+    let rs = host_call!("blockless_cgi", "cgi_status", unsafe {
+        cgi_status(handle, &mut status)
+    });
+    if rs != 0 {
+        return Err(CGIErrorKind::StatusError);
+    }
+    Ok(status)
+}
+
 impl CGICommand {
     fn new(command: String, args: Vec<String>, envs: Vec<CGIEnv>) -> Self {
         Self {
             command,
             args,
             envs,
+            timeout: None,
+            transport: CGITransport::default(),
+            stdin: StdinEncoder::default(),
             handle: None,
         }
     }
 
+    pub fn set_transport(&mut self, transport: CGITransport) {
+        self.transport = transport;
+    }
+
     pub fn exec(&mut self) -> Result<(), CGIErrorKind> {
         let mut handle = 0u32;
         let parmas = self.json_params();
-        unsafe {
-            let rs = cgi_open(parmas.as_ptr(), parmas.len() as _, &mut handle);
-            if rs != 0 {
-                return Err(CGIErrorKind::ExecError);
-            }
-        };
+        let rs = host_call!("blockless_cgi", "cgi_open", parmas.as_bytes(), unsafe {
+            cgi_open(parmas.as_ptr(), parmas.len() as _, &mut handle)
+        });
+        if rs != 0 {
+            return Err(CGIErrorKind::ExecError);
+        }
         self.handle = Some(handle);
         Ok(())
     }
 
-    fn read_all(&mut self, read_call: ReadFn) -> Result<Vec<u8>, CGIErrorKind> {
-        let mut readn = 0u32;
-        let mut data: Vec<u8> = Vec::new();
-        if self.handle.is_none() {
-            return Ok(data);
-        }
-        let handle = self.handle.unwrap();
-        let mut bs = [0u8; 1024];
-        loop {
-            unsafe {
-                let rs = read_call(handle, &mut bs as _, bs.len() as _, &mut readn);
-                if rs != 0 {
-                    return Err(CGIErrorKind::ReadError);
-                }
-                if readn == 0 {
-                    break;
-                }
-                data.extend_from_slice(&bs[..readn as _]);
-            }
-        }
-        Ok(data)
+    /// Starts the command for interactive use: write its stdin and read its
+    /// output incrementally instead of exec-then-read-all.
+    pub fn spawn(mut self) -> Result<CGIProcess, CGIErrorKind> {
+        self.exec()?;
+        let handle = self.handle.take().ok_or(CGIErrorKind::ExecError)?;
+        Ok(CGIProcess {
+            handle,
+            deadline: self.timeout.map(|timeout| Instant::now() + timeout),
+            transport: self.transport,
+            stdin: std::mem::take(&mut self.stdin),
+        })
+    }
+
+    fn read_all(&mut self, name: &str, read_call: ReadFn) -> Result<Vec<u8>, CGIErrorKind> {
+        let Some(handle) = self.handle else {
+            return Ok(Vec::new());
+        };
+        read_to_end(handle, name, read_call)
     }
 
     pub fn read_all_stdin(&mut self) -> Result<Vec<u8>, CGIErrorKind> {
-        self.read_all(cgi_stdout_read)
+        self.read_all("cgi_stdout_read", cgi_stdout_read)
     }
 
     pub fn read_all_stderr(&mut self) -> Result<Vec<u8>, CGIErrorKind> {
-        self.read_all(cgi_stderr_read)
+        self.read_all("cgi_stderr_read", cgi_stderr_read)
+    }
+
+    /// Blocks until the command started by `exec` exits and returns its exit status.
+    #[cfg(feature = "cgi-status")]
+    pub fn wait_status(&mut self) -> Result<i32, CGIErrorKind> {
+        exit_status(self.handle.ok_or(CGIErrorKind::ExecError)?)
+    }
+
+    /// Writes to the stdin of the command started by `exec`, returning how
+    /// many bytes the host accepted; with base64 transport, all of `buf`.
+    pub fn write_stdin(&mut self, buf: &[u8]) -> Result<u32, CGIErrorKind> {
+        let handle = self.handle.ok_or(CGIErrorKind::ExecError)?;
+        write_stdin(handle, self.transport, &mut self.stdin, buf)
+    }
+
+    pub fn write_all_stdin(&mut self, input: &[u8]) -> Result<(), CGIErrorKind> {
+        let handle = self.handle.ok_or(CGIErrorKind::ExecError)?;
+        write_all(handle, &self.stdin.encode(self.transport, input)?)
+    }
+
+    /// Runs the command to completion, collecting stdout, stderr and the exit status.
+    /// Like `CGIProcess::wait`, this can deadlock on a child that writes more
+    /// than a pipe buffer to stderr before writing to stdout.
+    pub fn exec_command(&mut self) -> Result<CGIOutput, CGIErrorKind> {
+        self.exec()?;
+        self.collect_output()
     }
 
-    pub fn exec_command(&mut self) -> Result<String, CGIErrorKind> {
+    /// Like `exec_command`, feeding `input` to the command's stdin first.
+    pub fn exec_with_input(&mut self, input: &[u8]) -> Result<CGIOutput, CGIErrorKind> {
         self.exec()?;
-        let bs = self.read_all_stdin()?;
-        String::from_utf8(bs).map_err(|_| CGIErrorKind::EncodingError)
+        self.write_all_stdin(input)?;
+        self.collect_output()
+    }
+
+    fn collect_output(&mut self) -> Result<CGIOutput, CGIErrorKind> {
+        let handle = self.handle.ok_or(CGIErrorKind::ExecError)?;
+        write_all(handle, &self.stdin.finish(self.transport))?;
+        let (stdout, stderr) = read_output(handle, None)?;
+        let stdout = self.transport.decode(stdout)?;
+        let stderr = self.transport.decode(stderr)?;
+        Ok(CGIOutput {
+            stdout,
+            stderr,
+            status: collected_status(handle),
+        })
     }
 
     fn json_params(&self) -> String {
@@ -118,31 +341,259 @@ impl CGICommand {
             })
             .collect::<Vec<_>>();
         obj.insert("envs", JsonValue::Array(envs));
+        if let Some(timeout) = self.timeout {
+            obj.insert("timeout", (timeout.as_millis() as u64).into());
+        }
+        if self.transport == CGITransport::Base64 {
+            obj.insert("transport", "base64".into());
+        }
         obj.dump()
     }
 }
 
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CGIOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    /// The exit status; `None` without the `cgi-status` feature or when the
+    /// host could not report it.
+    pub status: Option<i32>,
+}
+
+impl CGIOutput {
+    /// `false` when the exit status is unknown.
+    pub fn success(&self) -> bool {
+        self.status == Some(0)
+    }
+}
+
+/// The exit status for `CGIOutput`: output that was already read is worth
+/// returning even when the status is not available.
+fn collected_status(_handle: u32) -> Option<i32> {
+    #[cfg(feature = "cgi-status")]
+    return exit_status(_handle).ok();
+    #[cfg(not(feature = "cgi-status"))]
+    None
+}
+
+/// Builds a `CGICommand` without assembling `CGIEnv` lists by hand.
+///
+/// ```ignore
+/// let output = CGICommandBuilder::new("ffmpeg")
+///     .arg("-version")
+///     .inherit_envs("FFMPEG_")
+///     .timeout_ms(5_000)
+///     .output()?;
+/// ```
+pub struct CGICommandBuilder {
+    command: String,
+    args: Vec<String>,
+    envs: Vec<CGIEnv>,
+    inherit_prefixes: Vec<String>,
+    timeout: Option<Duration>,
+    transport: CGITransport,
+}
+
+impl CGICommandBuilder {
+    pub fn new(command: &str) -> Self {
+        CGICommandBuilder {
+            command: command.to_string(),
+            args: Vec::new(),
+            envs: Vec::new(),
+            inherit_prefixes: Vec::new(),
+            timeout: None,
+            transport: CGITransport::default(),
+        }
+    }
+
+    pub fn arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.to_string());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn env(mut self, name: &str, value: &str) -> Self {
+        self.envs.push(CGIEnv {
+            name: name.to_string(),
+            value: value.to_string(),
+        });
+        self
+    }
+
+    /// Passes on the app's environment variables whose name starts with
+    /// `prefix`; an empty prefix passes all of them. Explicit `env` calls win.
+    pub fn inherit_envs(mut self, prefix: &str) -> Self {
+        self.inherit_prefixes.push(prefix.to_string());
+        self
+    }
+
+    /// Asks the host to kill the command after `ms`; `output` also gives up
+    /// waiting once it passes.
+    pub fn timeout_ms(mut self, ms: u64) -> Self {
+        self.timeout = Some(Duration::from_millis(ms));
+        self
+    }
+
+    pub fn transport(mut self, transport: CGITransport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Checks the command against the installed extensions and resolves the env.
+    pub fn build(self) -> Result<CGICommand, CGIErrorKind> {
+        let mut envs = Vec::new();
+        if !self.inherit_prefixes.is_empty() {
+            for (name, value) in app_env_vars()? {
+                let explicit = self.envs.iter().any(|env| env.name == name);
+                let inherited = self.inherit_prefixes.iter().any(|p| name.starts_with(p));
+                if inherited && !explicit {
+                    envs.push(CGIEnv { name, value });
+                }
+            }
+        }
+        envs.extend(self.envs);
+        let mut command = CGIListExtensions::new()?.command(&self.command, self.args, envs)?;
+        command.timeout = self.timeout;
+        command.transport = self.transport;
+        Ok(command)
+    }
+
+    pub fn spawn(self) -> Result<CGIProcess, CGIErrorKind> {
+        self.build()?.spawn()
+    }
+
+    /// Runs the command to completion, collecting stdout, stderr and the exit status.
+    pub fn output(self) -> Result<CGIOutput, CGIErrorKind> {
+        self.spawn()?.wait()
+    }
+
+    /// Like `output`, feeding `input` to the command's stdin first.
+    pub fn output_with_input(self, input: &[u8]) -> Result<CGIOutput, CGIErrorKind> {
+        let mut process = self.spawn()?;
+        process.write_all_stdin(input)?;
+        process.wait()
+    }
+}
+
+/// The app's environment variables as the host hands them over: a JSON object,
+/// or `NAME=value` lines.
+fn app_env_vars() -> Result<Vec<(String, String)>, CGIErrorKind> {
+    let buf = read_env_vars_to_end().map_err(|_| CGIErrorKind::EnvError)?;
+    let s = std::str::from_utf8(&buf).map_err(|_| CGIErrorKind::EncodingError)?;
+    parse_env_vars(s)
+}
+
+fn parse_env_vars(s: &str) -> Result<Vec<(String, String)>, CGIErrorKind> {
+    if s.trim_start().starts_with('{') {
+        let json = json::parse(s).map_err(|_| CGIErrorKind::JsonDecodingError)?;
+        return Ok(json
+            .entries()
+            .filter_map(|(name, value)| Some((name.to_string(), value.as_str()?.to_string())))
+            .collect());
+    }
+    Ok(s.lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect())
+}
+
+/// A running CGI extension; the handle is closed on drop.
+pub struct CGIProcess {
+    handle: u32,
+    deadline: Option<Instant>,
+    transport: CGITransport,
+    stdin: StdinEncoder,
+}
+
+impl CGIProcess {
+    /// Writes to the process stdin, returning how many bytes the host
+    /// accepted; with base64 transport, all of `buf`.
+    pub fn write_stdin(&mut self, buf: &[u8]) -> Result<u32, CGIErrorKind> {
+        write_stdin(self.handle, self.transport, &mut self.stdin, buf)
+    }
+
+    pub fn write_all_stdin(&mut self, input: &[u8]) -> Result<(), CGIErrorKind> {
+        write_all(self.handle, &self.stdin.encode(self.transport, input)?)
+    }
+
+    /// Reads the next chunk of stdout; 0 once the process closed it.
+    pub fn read_stdout_chunk(&mut self, buf: &mut [u8]) -> Result<u32, CGIErrorKind> {
+        read_chunk(self.handle, "cgi_stdout_read", cgi_stdout_read, buf)
+    }
+
+    /// Reads the next chunk of stderr; 0 once the process closed it.
+    pub fn read_stderr_chunk(&mut self, buf: &mut [u8]) -> Result<u32, CGIErrorKind> {
+        read_chunk(self.handle, "cgi_stderr_read", cgi_stderr_read, buf)
+    }
+
+    /// Reads the remaining stdout and stderr, interleaved, until the process
+    /// closes them, then collects the exit status.
+    ///
+    /// Reads block, so a child that writes more than a pipe buffer to stderr
+    /// before writing to stdout deadlocks this call until the host's command
+    /// timeout kills it. Run such commands through a wrapper that merges or
+    /// discards stderr.
+    pub fn wait(mut self) -> Result<CGIOutput, CGIErrorKind> {
+        write_all(self.handle, &self.stdin.finish(self.transport))?;
+        let (stdout, stderr) = read_output(self.handle, self.deadline)?;
+        let stdout = self.transport.decode(stdout)?;
+        let stderr = self.transport.decode(stderr)?;
+        Ok(CGIOutput {
+            stdout,
+            stderr,
+            status: collected_status(self.handle),
+        })
+    }
+
+    /// Blocks until the process exits and returns its exit status.
+    #[cfg(feature = "cgi-status")]
+    pub fn wait_status(&mut self) -> Result<i32, CGIErrorKind> {
+        exit_status(self.handle)
+    }
+
+    /// Stops the process by closing its handle.
+    pub fn kill(self) {
+        drop(self)
+    }
+}
+
+impl Drop for CGIProcess {
+    fn drop(&mut self) {
+        host_call!("blockless_cgi", "cgi_close", unsafe {
+            cgi_close(self.handle)
+        });
+    }
+}
+
 pub struct CGIListExtensions {
     handle: u32,
 }
 
 impl Drop for CGIListExtensions {
     fn drop(&mut self) {
-        unsafe {
-            cgi_close(self.handle);
-        }
+        host_call!("blockless_cgi", "cgi_close", unsafe {
+            cgi_close(self.handle)
+        });
     }
 }
 
 impl CGIListExtensions {
     pub fn new() -> Result<Self, CGIErrorKind> {
         let mut cgi_handle: u32 = 0;
-        unsafe {
-            let rs = cgi_list_exec(&mut cgi_handle as *mut u32);
-            if rs != 0 {
-                return Err(CGIErrorKind::ListError);
-            }
-        };
+        let rs = host_call!("blockless_cgi", "cgi_list_exec", unsafe {
+            cgi_list_exec(&mut cgi_handle as *mut u32)
+        });
+        if rs != 0 {
+            return Err(CGIErrorKind::ListError);
+        }
         Ok(CGIListExtensions { handle: cgi_handle })
     }
 
@@ -151,16 +602,19 @@ impl CGIListExtensions {
         let mut bs = [0u8; 1024];
         let mut readn = 0u32;
         loop {
-            unsafe {
-                let rs = cgi_list_read(self.handle, &mut bs as _, bs.len() as _, &mut readn);
-                if rs != 0 {
-                    return Err(CGIErrorKind::ListError);
-                }
-                if readn == 0 {
-                    break;
-                }
-                data.extend_from_slice(&bs[..readn as _]);
+            let rs = host_call!(
+                "blockless_cgi",
+                "cgi_list_read",
+                crate::ffi_debug::written(&bs, readn),
+                unsafe { cgi_list_read(self.handle, &mut bs as _, bs.len() as _, &mut readn) }
+            );
+            if rs != 0 {
+                return Err(CGIErrorKind::ListError);
             }
+            if readn == 0 {
+                break;
+            }
+            data.extend_from_slice(&bs[..readn as _]);
         }
         Ok(data)
     }
@@ -174,7 +628,7 @@ impl CGIListExtensions {
         let extensions = self.list()?;
         extensions
             .iter()
-            .find(|ext| if &ext.alias == command { true } else { false })
+            .find(|ext| ext.alias == command)
             .map(|_| CGICommand::new(command.to_string(), args, envs))
             .ok_or(CGIErrorKind::NoCommandError)
     }
@@ -201,3 +655,51 @@ impl CGIListExtensions {
         Ok(externs)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_stdin_is_one_stream_across_writes() {
+        let mut stdin = StdinEncoder::default();
+        let mut sent = Vec::new();
+        for part in [&b"f"[..], b"oo", b"", b"ba", b"r!"] {
+            sent.extend_from_slice(&stdin.encode(CGITransport::Base64, part).unwrap());
+        }
+        sent.extend(stdin.finish(CGITransport::Base64));
+        let sent = String::from_utf8(sent).unwrap();
+        assert_eq!(sent, base64::encode(b"foobar!"));
+        assert_eq!(base64::decode(&sent).unwrap(), b"foobar!");
+        // finishing twice does not pad twice, and writing after the end fails
+        assert!(stdin.finish(CGITransport::Base64).is_empty());
+        assert!(matches!(
+            stdin.encode(CGITransport::Base64, b"x"),
+            Err(CGIErrorKind::WriteError)
+        ));
+    }
+
+    #[test]
+    fn raw_stdin_passes_through() {
+        let mut stdin = StdinEncoder::default();
+        assert_eq!(&*stdin.encode(CGITransport::Raw, b"ab").unwrap(), b"ab");
+        assert!(stdin.finish(CGITransport::Raw).is_empty());
+        assert_eq!(&*stdin.encode(CGITransport::Raw, b"c").unwrap(), b"c");
+    }
+
+    #[test]
+    fn env_vars_from_json_or_lines() {
+        let vars = parse_env_vars(r#"{"A":"1","B":"x=y","N":2}"#).unwrap();
+        assert_eq!(vars, [("A".into(), "1".into()), ("B".into(), "x=y".into())]);
+        let vars = parse_env_vars("A=1\nB=x=y\nnoequals").unwrap();
+        assert_eq!(vars, [("A".into(), "1".into()), ("B".into(), "x=y".into())]);
+    }
+
+    #[test]
+    fn truncated_json_env_is_an_error() {
+        assert!(matches!(
+            parse_env_vars(r#"{"A":"1","B":"x="#),
+            Err(CGIErrorKind::JsonDecodingError)
+        ));
+    }
+}
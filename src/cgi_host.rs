@@ -10,9 +10,14 @@ extern "C" {
     pub(crate) fn cgi_stderr_read(handle: u32, buf: *mut u8, buf_len: u32, num: *mut u32) -> u32;
 
     #[link_name = "cgi_stdin_write"]
-    #[allow(dead_code)]
     pub(crate) fn cgi_stdin_write(handle: u32, buf: *const u8, buf_len: u32, num: *mut u32) -> u32;
 
+    /// Blocks until the command exits and writes its exit status to `status`;
+    /// returns 0 on success. Only imported with the `cgi-status` feature.
+    #[cfg(feature = "cgi-status")]
+    #[link_name = "cgi_status"]
+    pub(crate) fn cgi_status(handle: u32, status: *mut i32) -> u32;
+
     #[link_name = "cgi_close"]
     pub(crate) fn cgi_close(handle: u32) -> u32;
 
@@ -0,0 +1,122 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{time::Deadline, ContextError};
+
+/// Deadline and cancellation flag shared by the calls of one function run.
+///
+/// Clones share the cancellation flag, so cancelling any clone cancels them all.
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    deadline: Option<Deadline>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_deadline(deadline: Deadline) -> Self {
+        Context {
+            deadline: Some(deadline),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self::with_deadline(Deadline::after(timeout))
+    }
+
+    /// Derives a context sharing this one's cancellation flag whose deadline
+    /// is the earlier of the parent's deadline and `timeout` from now.
+    pub fn child(&self, timeout: Duration) -> Self {
+        Context {
+            deadline: Some(
+                self.deadline
+                    .map_or_else(|| Deadline::after(timeout), |d| d.child(timeout)),
+            ),
+            cancelled: self.cancelled.clone(),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn deadline(&self) -> Option<Deadline> {
+        self.deadline
+    }
+
+    /// Time left before the deadline, `None` if the context has no deadline
+    /// or its deadline never expires.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .filter(|d| !d.is_never())
+            .map(|d| d.remaining())
+    }
+
+    pub fn check(&self) -> Result<(), ContextError> {
+        if self.is_cancelled() {
+            return Err(ContextError::Cancelled);
+        }
+        match self.remaining() {
+            Some(remaining) if remaining.is_zero() => Err(ContextError::DeadlineExceeded),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn child_keeps_the_earlier_deadline() {
+        let parent = Context::with_timeout(Duration::from_secs(1));
+        let child = parent.child(Duration::from_secs(60));
+        assert!(child.deadline() <= parent.deadline());
+        assert!(child.remaining().unwrap() <= Duration::from_secs(1));
+
+        let child = parent.child(Duration::from_millis(10));
+        assert!(child.deadline() < parent.deadline());
+
+        let child = Context::new().child(Duration::from_secs(5));
+        assert!(child.remaining().unwrap() <= Duration::from_secs(5));
+    }
+
+    #[test]
+    fn cancel_is_shared_with_children_and_clones() {
+        let parent = Context::new();
+        let child = parent.child(Duration::from_secs(60));
+        let clone = parent.clone();
+        assert_eq!(child.check(), Ok(()));
+        child.cancel();
+        assert!(parent.is_cancelled());
+        assert_eq!(clone.check(), Err(ContextError::Cancelled));
+    }
+
+    #[test]
+    fn check_fails_once_the_deadline_passes() {
+        assert_eq!(
+            Context::with_timeout(Duration::ZERO).check(),
+            Err(ContextError::DeadlineExceeded)
+        );
+        assert_eq!(
+            Context::with_timeout(Duration::from_secs(60)).check(),
+            Ok(())
+        );
+        assert_eq!(Context::with_deadline(Deadline::never()).check(), Ok(()));
+        assert_eq!(Context::with_deadline(Deadline::never()).remaining(), None);
+        assert_eq!(Context::new().remaining(), None);
+    }
+}
@@ -1,3 +1,5 @@
+pub mod codes;
+
 #[derive(Debug)]
 pub enum HttpErrorKind {
     InvalidDriver,
@@ -14,6 +16,15 @@ pub enum HttpErrorKind {
     RuntimeError,
     TooManySessions,
     PermissionDeny,
+    ConnectTimeout,
+    ReadTimeout,
+    Cancelled,
+    DeadlineExceeded,
+    DecompressionError,
+    ResponseTooLarge,
+    BodyReadError,
+    BlockedByPolicy,
+    InvalidMultipart,
 }
 
 impl std::error::Error for HttpErrorKind {}
@@ -35,37 +46,94 @@ impl std::fmt::Display for HttpErrorKind {
             Self::RuntimeError => write!(f, "Runtime error"),
             Self::TooManySessions => write!(f, "Too many sessions"),
             Self::PermissionDeny => write!(f, "Permision deny."),
+            Self::ConnectTimeout => write!(f, "Connect timeout"),
+            Self::ReadTimeout => write!(f, "Read timeout"),
+            Self::Cancelled => write!(f, "Cancelled"),
+            Self::DeadlineExceeded => write!(f, "Deadline exceeded"),
+            Self::DecompressionError => write!(f, "Decompression error"),
+            Self::ResponseTooLarge => write!(f, "Response too large"),
+            Self::BodyReadError => write!(f, "Body read error"),
+            Self::BlockedByPolicy => write!(f, "Blocked by url policy"),
+            Self::InvalidMultipart => write!(f, "Invalid multipart body"),
+        }
+    }
+}
+
+impl From<ContextError> for HttpErrorKind {
+    fn from(e: ContextError) -> HttpErrorKind {
+        match e {
+            ContextError::Cancelled => HttpErrorKind::Cancelled,
+            ContextError::DeadlineExceeded => HttpErrorKind::DeadlineExceeded,
         }
     }
 }
 
 impl From<u32> for HttpErrorKind {
     fn from(i: u32) -> HttpErrorKind {
+        use codes::http::*;
         match i {
-            1 => HttpErrorKind::InvalidHandle,
-            2 => HttpErrorKind::MemoryAccessError,
-            3 => HttpErrorKind::BufferTooSmall,
-            4 => HttpErrorKind::HeaderNotFound,
-            5 => HttpErrorKind::Utf8Error,
-            6 => HttpErrorKind::DestinationNotAllowed,
-            7 => HttpErrorKind::InvalidMethod,
-            8 => HttpErrorKind::InvalidEncoding,
-            9 => HttpErrorKind::InvalidUrl,
-            10 => HttpErrorKind::RequestError,
-            11 => HttpErrorKind::RuntimeError,
-            12 => HttpErrorKind::TooManySessions,
-            13 => HttpErrorKind::PermissionDeny,
+            INVALID_HANDLE => HttpErrorKind::InvalidHandle,
+            MEMORY_ACCESS_ERROR => HttpErrorKind::MemoryAccessError,
+            BUFFER_TOO_SMALL => HttpErrorKind::BufferTooSmall,
+            HEADER_NOT_FOUND => HttpErrorKind::HeaderNotFound,
+            UTF8_ERROR => HttpErrorKind::Utf8Error,
+            DESTINATION_NOT_ALLOWED => HttpErrorKind::DestinationNotAllowed,
+            INVALID_METHOD => HttpErrorKind::InvalidMethod,
+            INVALID_ENCODING => HttpErrorKind::InvalidEncoding,
+            INVALID_URL => HttpErrorKind::InvalidUrl,
+            REQUEST_ERROR => HttpErrorKind::RequestError,
+            RUNTIME_ERROR => HttpErrorKind::RuntimeError,
+            TOO_MANY_SESSIONS => HttpErrorKind::TooManySessions,
+            PERMISSION_DENY => HttpErrorKind::PermissionDeny,
+            CONNECT_TIMEOUT => HttpErrorKind::ConnectTimeout,
+            READ_TIMEOUT => HttpErrorKind::ReadTimeout,
             _ => HttpErrorKind::RuntimeError,
         }
     }
 }
 
+impl HttpErrorKind {
+    /// The host code this error maps from, `None` for errors raised by the SDK itself.
+    pub fn code(&self) -> Option<u32> {
+        use codes::http::*;
+        match self {
+            HttpErrorKind::InvalidHandle => Some(INVALID_HANDLE),
+            HttpErrorKind::MemoryAccessError => Some(MEMORY_ACCESS_ERROR),
+            HttpErrorKind::BufferTooSmall => Some(BUFFER_TOO_SMALL),
+            HttpErrorKind::HeaderNotFound => Some(HEADER_NOT_FOUND),
+            HttpErrorKind::Utf8Error => Some(UTF8_ERROR),
+            HttpErrorKind::DestinationNotAllowed => Some(DESTINATION_NOT_ALLOWED),
+            HttpErrorKind::InvalidMethod => Some(INVALID_METHOD),
+            HttpErrorKind::InvalidEncoding => Some(INVALID_ENCODING),
+            HttpErrorKind::InvalidUrl => Some(INVALID_URL),
+            HttpErrorKind::RequestError => Some(REQUEST_ERROR),
+            HttpErrorKind::RuntimeError => Some(RUNTIME_ERROR),
+            HttpErrorKind::TooManySessions => Some(TOO_MANY_SESSIONS),
+            HttpErrorKind::PermissionDeny => Some(PERMISSION_DENY),
+            HttpErrorKind::ConnectTimeout => Some(CONNECT_TIMEOUT),
+            HttpErrorKind::ReadTimeout => Some(READ_TIMEOUT),
+            HttpErrorKind::InvalidDriver
+            | HttpErrorKind::Cancelled
+            | HttpErrorKind::DeadlineExceeded
+            | HttpErrorKind::DecompressionError
+            | HttpErrorKind::ResponseTooLarge
+            | HttpErrorKind::BodyReadError
+            | HttpErrorKind::BlockedByPolicy
+            | HttpErrorKind::InvalidMultipart => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum SocketErrorKind {
     ConnectRefused,
     ParameterError,
     ConnectionReset,
     AddressInUse,
+    NotConnected,
+    InvalidHandle,
+    TimedOut,
+    Unknown(u32),
 }
 
 impl std::fmt::Display for SocketErrorKind {
@@ -75,12 +143,126 @@ impl std::fmt::Display for SocketErrorKind {
             SocketErrorKind::ParameterError => write!(f, "Parameter Error."),
             SocketErrorKind::ConnectionReset => write!(f, "Connection  Reset."),
             SocketErrorKind::AddressInUse => write!(f, "Address In Use."),
+            SocketErrorKind::NotConnected => write!(f, "Not Connected."),
+            SocketErrorKind::InvalidHandle => write!(f, "Invalid Handle."),
+            SocketErrorKind::TimedOut => write!(f, "Timed Out."),
+            SocketErrorKind::Unknown(code) => write!(f, "Unknown Error: {}.", code),
         }
     }
 }
 
 impl std::error::Error for SocketErrorKind {}
 
+impl From<u32> for SocketErrorKind {
+    fn from(i: u32) -> SocketErrorKind {
+        use codes::socket::*;
+        match i {
+            CONNECT_REFUSED => SocketErrorKind::ConnectRefused,
+            PARAMETER_ERROR => SocketErrorKind::ParameterError,
+            CONNECTION_RESET => SocketErrorKind::ConnectionReset,
+            ADDRESS_IN_USE => SocketErrorKind::AddressInUse,
+            NOT_CONNECTED => SocketErrorKind::NotConnected,
+            INVALID_HANDLE => SocketErrorKind::InvalidHandle,
+            TIMED_OUT => SocketErrorKind::TimedOut,
+            _ => SocketErrorKind::Unknown(i),
+        }
+    }
+}
+
+impl From<SocketErrorKind> for std::io::Error {
+    fn from(e: SocketErrorKind) -> std::io::Error {
+        use std::io::ErrorKind;
+        let kind = match e {
+            SocketErrorKind::ConnectRefused => ErrorKind::ConnectionRefused,
+            SocketErrorKind::ParameterError => ErrorKind::InvalidInput,
+            SocketErrorKind::ConnectionReset => ErrorKind::ConnectionReset,
+            SocketErrorKind::AddressInUse => ErrorKind::AddrInUse,
+            SocketErrorKind::NotConnected => ErrorKind::NotConnected,
+            SocketErrorKind::InvalidHandle => ErrorKind::InvalidInput,
+            SocketErrorKind::TimedOut => ErrorKind::TimedOut,
+            SocketErrorKind::Unknown(_) => ErrorKind::Other,
+        };
+        std::io::Error::new(kind, e)
+    }
+}
+
+impl SocketErrorKind {
+    pub fn code(&self) -> u32 {
+        use codes::socket::*;
+        match self {
+            SocketErrorKind::ConnectRefused => CONNECT_REFUSED,
+            SocketErrorKind::ParameterError => PARAMETER_ERROR,
+            SocketErrorKind::ConnectionReset => CONNECTION_RESET,
+            SocketErrorKind::AddressInUse => ADDRESS_IN_USE,
+            SocketErrorKind::NotConnected => NOT_CONNECTED,
+            SocketErrorKind::InvalidHandle => INVALID_HANDLE,
+            SocketErrorKind::TimedOut => TIMED_OUT,
+            SocketErrorKind::Unknown(code) => *code,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventErrorKind {
+    InvalidHandle,
+    InvalidTopic,
+    BufferTooSmall,
+    TimedOut,
+    Closed,
+    PermissionDeny,
+    /// The event envelope or payload could not be decoded.
+    DecodeError(String),
+    Unknown(u32),
+}
+
+impl std::fmt::Display for EventErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventErrorKind::InvalidHandle => write!(f, "Invalid Handle."),
+            EventErrorKind::InvalidTopic => write!(f, "Invalid Topic."),
+            EventErrorKind::BufferTooSmall => write!(f, "Buffer Too Small."),
+            EventErrorKind::TimedOut => write!(f, "Timed Out."),
+            EventErrorKind::Closed => write!(f, "Subscription Closed."),
+            EventErrorKind::PermissionDeny => write!(f, "Permission Deny."),
+            EventErrorKind::DecodeError(msg) => write!(f, "Event Decode Error: {}.", msg),
+            EventErrorKind::Unknown(code) => write!(f, "Unknown Error: {}.", code),
+        }
+    }
+}
+
+impl std::error::Error for EventErrorKind {}
+
+impl From<u32> for EventErrorKind {
+    fn from(i: u32) -> EventErrorKind {
+        use codes::events::*;
+        match i {
+            INVALID_HANDLE => EventErrorKind::InvalidHandle,
+            INVALID_TOPIC => EventErrorKind::InvalidTopic,
+            BUFFER_TOO_SMALL => EventErrorKind::BufferTooSmall,
+            TIMED_OUT => EventErrorKind::TimedOut,
+            CLOSED => EventErrorKind::Closed,
+            PERMISSION_DENY => EventErrorKind::PermissionDeny,
+            _ => EventErrorKind::Unknown(i),
+        }
+    }
+}
+
+impl EventErrorKind {
+    pub fn code(&self) -> Option<u32> {
+        use codes::events::*;
+        match self {
+            EventErrorKind::InvalidHandle => Some(INVALID_HANDLE),
+            EventErrorKind::InvalidTopic => Some(INVALID_TOPIC),
+            EventErrorKind::BufferTooSmall => Some(BUFFER_TOO_SMALL),
+            EventErrorKind::TimedOut => Some(TIMED_OUT),
+            EventErrorKind::Closed => Some(CLOSED),
+            EventErrorKind::PermissionDeny => Some(PERMISSION_DENY),
+            EventErrorKind::DecodeError(_) => None,
+            EventErrorKind::Unknown(code) => Some(*code),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum CGIErrorKind {
     ListError,
@@ -88,6 +270,10 @@ pub enum CGIErrorKind {
     JsonDecodingError,
     ExecError,
     ReadError,
+    WriteError,
+    StatusError,
+    EnvError,
+    TimedOut,
     NoCommandError,
 }
 
@@ -99,9 +285,246 @@ impl std::fmt::Display for CGIErrorKind {
             CGIErrorKind::JsonDecodingError => write!(f, "Json decoding Error."),
             CGIErrorKind::ExecError => write!(f, "CGI Exec Error."),
             CGIErrorKind::ReadError => write!(f, "Read Error."),
+            CGIErrorKind::WriteError => write!(f, "Write Error."),
+            CGIErrorKind::StatusError => write!(f, "CGI Status Error."),
+            CGIErrorKind::EnvError => write!(f, "Env Vars Read Error."),
+            CGIErrorKind::TimedOut => write!(f, "CGI Command Timed Out."),
             CGIErrorKind::NoCommandError => write!(f, "No CGI Command Error."),
         }
     }
 }
 
 impl std::error::Error for CGIErrorKind {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextError {
+    Cancelled,
+    DeadlineExceeded,
+}
+
+impl std::fmt::Display for ContextError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            ContextError::Cancelled => write!(f, "Context cancelled."),
+            ContextError::DeadlineExceeded => write!(f, "Context deadline exceeded."),
+        }
+    }
+}
+
+impl std::error::Error for ContextError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeoutExceeded {
+    pub stage: Option<String>,
+    pub budget: std::time::Duration,
+    pub elapsed: std::time::Duration,
+}
+
+impl std::fmt::Display for TimeoutExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.stage {
+            Some(stage) => write!(f, "Stage {} ", stage)?,
+            None => write!(f, "Timeout ")?,
+        }
+        write!(
+            f,
+            "exceeded: took {}ms of {}ms budget.",
+            self.elapsed.as_millis(),
+            self.budget.as_millis()
+        )
+    }
+}
+
+impl std::error::Error for TimeoutExceeded {}
+
+/// A rate that is not a finite, positive number of tokens per second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvalidRate(pub f64);
+
+impl std::fmt::Display for InvalidRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid rate limit: {} tokens per second; must be finite and positive.",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidRate {}
+
+#[derive(Debug)]
+pub enum RuntimeErrorKind {
+    NotSupported,
+    NotMetered,
+    Unknown(u32),
+}
+
+impl std::fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            RuntimeErrorKind::NotSupported => write!(f, "Not supported by the runtime."),
+            RuntimeErrorKind::NotMetered => write!(f, "Instance is not metered."),
+            RuntimeErrorKind::Unknown(code) => write!(f, "Unknown Error: {}.", code),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeErrorKind {}
+
+impl From<u32> for RuntimeErrorKind {
+    fn from(i: u32) -> RuntimeErrorKind {
+        use codes::runtime::*;
+        match i {
+            NOT_SUPPORTED => RuntimeErrorKind::NotSupported,
+            NOT_METERED => RuntimeErrorKind::NotMetered,
+            _ => RuntimeErrorKind::Unknown(i),
+        }
+    }
+}
+
+impl RuntimeErrorKind {
+    pub fn code(&self) -> u32 {
+        use codes::runtime::*;
+        match self {
+            RuntimeErrorKind::NotSupported => NOT_SUPPORTED,
+            RuntimeErrorKind::NotMetered => NOT_METERED,
+            RuntimeErrorKind::Unknown(code) => *code,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_socket_codes_map_to_their_kind() {
+        use codes::socket::*;
+        assert!(matches!(
+            SocketErrorKind::from(CONNECT_REFUSED),
+            SocketErrorKind::ConnectRefused
+        ));
+        assert!(matches!(
+            SocketErrorKind::from(ADDRESS_IN_USE),
+            SocketErrorKind::AddressInUse
+        ));
+        assert!(matches!(
+            SocketErrorKind::from(TIMED_OUT),
+            SocketErrorKind::TimedOut
+        ));
+    }
+
+    #[test]
+    fn unknown_socket_codes_are_kept() {
+        for code in [0, 8, 999, u32::MAX] {
+            let kind = SocketErrorKind::from(code);
+            assert!(matches!(kind, SocketErrorKind::Unknown(c) if c == code));
+            assert_eq!(kind.code(), code);
+            assert_eq!(kind.to_string(), format!("Unknown Error: {}.", code));
+            let io: std::io::Error = kind.into();
+            assert_eq!(io.kind(), std::io::ErrorKind::Other);
+        }
+    }
+
+    #[test]
+    fn http_codes_round_trip() {
+        use codes::http::*;
+        let all = [
+            INVALID_HANDLE,
+            MEMORY_ACCESS_ERROR,
+            BUFFER_TOO_SMALL,
+            HEADER_NOT_FOUND,
+            UTF8_ERROR,
+            DESTINATION_NOT_ALLOWED,
+            INVALID_METHOD,
+            INVALID_ENCODING,
+            INVALID_URL,
+            REQUEST_ERROR,
+            RUNTIME_ERROR,
+            TOO_MANY_SESSIONS,
+            PERMISSION_DENY,
+            CONNECT_TIMEOUT,
+            READ_TIMEOUT,
+        ];
+        assert_eq!(all.to_vec(), (1..=all.len() as u32).collect::<Vec<_>>());
+        for code in all {
+            assert_eq!(
+                HttpErrorKind::from(code).code(),
+                Some(code),
+                "code {}",
+                code
+            );
+        }
+        // unknown codes fall back to a runtime error
+        assert_eq!(HttpErrorKind::from(999).code(), Some(RUNTIME_ERROR));
+    }
+
+    #[test]
+    fn socket_codes_round_trip() {
+        use codes::socket::*;
+        let all = [
+            CONNECT_REFUSED,
+            PARAMETER_ERROR,
+            CONNECTION_RESET,
+            ADDRESS_IN_USE,
+            NOT_CONNECTED,
+            INVALID_HANDLE,
+            TIMED_OUT,
+        ];
+        assert_eq!(all.to_vec(), (1..=all.len() as u32).collect::<Vec<_>>());
+        for code in all.into_iter().chain([0, 100]) {
+            assert_eq!(SocketErrorKind::from(code).code(), code);
+        }
+    }
+
+    #[test]
+    fn event_codes_round_trip() {
+        use codes::events::*;
+        let all = [
+            INVALID_HANDLE,
+            INVALID_TOPIC,
+            BUFFER_TOO_SMALL,
+            TIMED_OUT,
+            CLOSED,
+            PERMISSION_DENY,
+        ];
+        assert_eq!(all.to_vec(), (1..=all.len() as u32).collect::<Vec<_>>());
+        for code in all.into_iter().chain([0, 100]) {
+            assert_eq!(EventErrorKind::from(code).code(), Some(code));
+        }
+        assert_eq!(EventErrorKind::DecodeError(String::new()).code(), None);
+    }
+
+    #[test]
+    fn runtime_codes_round_trip() {
+        use codes::runtime::*;
+        for code in [NOT_SUPPORTED, NOT_METERED, 0, 100] {
+            assert_eq!(RuntimeErrorKind::from(code).code(), code);
+        }
+    }
+
+    #[test]
+    fn sdk_side_http_errors_have_no_code() {
+        for kind in [
+            HttpErrorKind::InvalidDriver,
+            HttpErrorKind::Cancelled,
+            HttpErrorKind::DeadlineExceeded,
+            HttpErrorKind::DecompressionError,
+            HttpErrorKind::ResponseTooLarge,
+            HttpErrorKind::BodyReadError,
+            HttpErrorKind::BlockedByPolicy,
+            HttpErrorKind::InvalidMultipart,
+        ] {
+            assert_eq!(kind.code(), None, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn socket_errors_convert_to_io_kinds() {
+        let io: std::io::Error = SocketErrorKind::ConnectRefused.into();
+        assert_eq!(io.kind(), std::io::ErrorKind::ConnectionRefused);
+        let io: std::io::Error = SocketErrorKind::TimedOut.into();
+        assert_eq!(io.kind(), std::io::ErrorKind::TimedOut);
+    }
+}
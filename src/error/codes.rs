@@ -0,0 +1,50 @@
+//! Numeric error codes returned by the host modules, in one place so the
+//! `From` conversions and their reverse `code()` mappings cannot drift apart.
+
+pub mod http {
+    pub const INVALID_HANDLE: u32 = 1;
+    pub const MEMORY_ACCESS_ERROR: u32 = 2;
+    pub const BUFFER_TOO_SMALL: u32 = 3;
+    pub const HEADER_NOT_FOUND: u32 = 4;
+    pub const UTF8_ERROR: u32 = 5;
+    pub const DESTINATION_NOT_ALLOWED: u32 = 6;
+    pub const INVALID_METHOD: u32 = 7;
+    pub const INVALID_ENCODING: u32 = 8;
+    pub const INVALID_URL: u32 = 9;
+    pub const REQUEST_ERROR: u32 = 10;
+    pub const RUNTIME_ERROR: u32 = 11;
+    pub const TOO_MANY_SESSIONS: u32 = 12;
+    pub const PERMISSION_DENY: u32 = 13;
+    pub const CONNECT_TIMEOUT: u32 = 14;
+    pub const READ_TIMEOUT: u32 = 15;
+}
+
+pub mod socket {
+    pub const CONNECT_REFUSED: u32 = 1;
+    pub const PARAMETER_ERROR: u32 = 2;
+    pub const CONNECTION_RESET: u32 = 3;
+    pub const ADDRESS_IN_USE: u32 = 4;
+    pub const NOT_CONNECTED: u32 = 5;
+    pub const INVALID_HANDLE: u32 = 6;
+    pub const TIMED_OUT: u32 = 7;
+}
+
+pub mod events {
+    pub const INVALID_HANDLE: u32 = 1;
+    pub const INVALID_TOPIC: u32 = 2;
+    pub const BUFFER_TOO_SMALL: u32 = 3;
+    pub const TIMED_OUT: u32 = 4;
+    pub const CLOSED: u32 = 5;
+    pub const PERMISSION_DENY: u32 = 6;
+}
+
+pub mod llm {
+    pub const MODEL_NOT_SET: i32 = 1;
+    pub const OPTIONS_NOT_SET: i32 = 2;
+    pub const UTF8_ERROR: i32 = 3;
+}
+
+pub mod runtime {
+    pub const NOT_SUPPORTED: u32 = 1;
+    pub const NOT_METERED: u32 = 2;
+}
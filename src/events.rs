@@ -0,0 +1,208 @@
+//! Host event subscriptions: timers, network events and custom topics.
+//!
+//! The host delivers each event as a JSON envelope
+//! `{"topic": "...", "timestamp": <unix ms>, "data": "..."}`.
+//!
+//! Needs a runtime that provides the `blockless_events` imports; enabled by the
+//! `events` feature.
+
+use std::time::Duration;
+
+use json::JsonValue;
+
+use crate::{events_host::*, stream::ItemStream, EventErrorKind};
+
+/// How long `ItemStream::next` waits in a single host poll before polling again.
+const LONG_POLL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// The topic the event was published on, which may be narrower than the
+    /// subscribed pattern.
+    pub topic: String,
+    pub timestamp_ms: Option<u64>,
+    pub data: String,
+}
+
+impl Event {
+    fn parse(raw: &[u8]) -> Result<Self, EventErrorKind> {
+        let text =
+            std::str::from_utf8(raw).map_err(|e| EventErrorKind::DecodeError(e.to_string()))?;
+        let json = json::parse(text).map_err(|e| EventErrorKind::DecodeError(e.to_string()))?;
+        let topic = json["topic"]
+            .as_str()
+            .ok_or_else(|| EventErrorKind::DecodeError("missing topic".into()))?;
+        // Hosts may hand structured payloads over as JSON rather than a string.
+        let data = match &json["data"] {
+            JsonValue::Null => String::new(),
+            data => data.as_str().map_or_else(|| data.dump(), str::to_string),
+        };
+        Ok(Event {
+            topic: topic.to_string(),
+            timestamp_ms: json["timestamp"].as_u64(),
+            data,
+        })
+    }
+
+    pub fn json(&self) -> Result<JsonValue, EventErrorKind> {
+        json::parse(&self.data).map_err(|e| EventErrorKind::DecodeError(e.to_string()))
+    }
+
+    /// The payload parsed as JSON and converted into `T`.
+    pub fn decode<T: TryFrom<JsonValue>>(&self) -> Result<T, EventErrorKind> {
+        T::try_from(self.json()?).map_err(|_| {
+            EventErrorKind::DecodeError("payload does not convert to the requested type".into())
+        })
+    }
+}
+
+/// Subscribes to `topic`; the host decides which topics exist (e.g. `timer/1s`)
+/// and whether a trailing `*` matches a whole family of them.
+pub fn subscribe(topic: &str) -> Result<EventStream, EventErrorKind> {
+    let mut handle = 0u32;
+    let rs = host_call!(
+        "blockless_events",
+        "events_subscribe",
+        topic.as_bytes(),
+        unsafe { events_subscribe(topic.as_ptr(), topic.len() as _, &mut handle) }
+    );
+    if rs != 0 {
+        return Err(EventErrorKind::from(rs));
+    }
+    Ok(EventStream {
+        handle,
+        buf: vec![0; 4096],
+        subscribed: true,
+    })
+}
+
+/// Publishes `data` on a custom topic for other subscribers.
+pub fn publish(topic: &str, data: &str) -> Result<(), EventErrorKind> {
+    let rs = host_call!(
+        "blockless_events",
+        "events_publish",
+        data.as_bytes(),
+        unsafe {
+            events_publish(
+                topic.as_ptr(),
+                topic.len() as _,
+                data.as_ptr(),
+                data.len() as _,
+            )
+        }
+    );
+    if rs != 0 {
+        return Err(EventErrorKind::from(rs));
+    }
+    Ok(())
+}
+
+pub struct EventStream {
+    handle: u32,
+    buf: Vec<u8>,
+    subscribed: bool,
+}
+
+impl EventStream {
+    pub fn handle(&self) -> u32 {
+        self.handle
+    }
+
+    /// Waits up to `timeout` for the next event; `Ok(None)` if none arrived in time.
+    pub fn poll_next(&mut self, timeout: Duration) -> Result<Option<Event>, EventErrorKind> {
+        let timeout_ms = timeout.as_millis().min(u32::MAX as u128) as u32;
+        loop {
+            let mut num = 0u32;
+            let rs = host_call!(
+                "blockless_events",
+                "events_poll",
+                crate::ffi_debug::written(&self.buf, num),
+                unsafe {
+                    events_poll(
+                        self.handle,
+                        timeout_ms,
+                        self.buf.as_mut_ptr(),
+                        self.buf.len() as _,
+                        &mut num,
+                    )
+                }
+            );
+            match EventErrorKind::from(rs) {
+                _ if rs == 0 => return Event::parse(&self.buf[..num as usize]).map(Some),
+                EventErrorKind::TimedOut => return Ok(None),
+                // The event stays queued and `num` holds the size it needs.
+                EventErrorKind::BufferTooSmall if num as usize > self.buf.len() => {
+                    self.buf.resize(num as usize, 0);
+                }
+                e => return Err(e),
+            }
+        }
+    }
+
+    pub fn unsubscribe(mut self) -> Result<(), EventErrorKind> {
+        match self.release() {
+            0 => Ok(()),
+            rs => Err(EventErrorKind::from(rs)),
+        }
+    }
+
+    fn release(&mut self) -> u32 {
+        if !std::mem::take(&mut self.subscribed) {
+            return 0;
+        }
+        host_call!("blockless_events", "events_unsubscribe", unsafe {
+            events_unsubscribe(self.handle)
+        })
+    }
+}
+
+/// Blocks until the next event, ending once the host closes the subscription.
+impl ItemStream<Event> for EventStream {
+    type Error = EventErrorKind;
+
+    fn next(&mut self) -> Option<Result<Event, EventErrorKind>> {
+        loop {
+            match self.poll_next(LONG_POLL) {
+                Ok(Some(event)) => return Some(Ok(event)),
+                Ok(None) => continue,
+                Err(EventErrorKind::Closed) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_envelopes() {
+        let event =
+            Event::parse(br#"{"topic":"timer/1s","timestamp":1700000000000,"data":"tick"}"#)
+                .unwrap();
+        assert_eq!(event.topic, "timer/1s");
+        assert_eq!(event.timestamp_ms, Some(1_700_000_000_000));
+        assert_eq!(event.data, "tick");
+
+        let event = Event::parse(br#"{"topic":"custom","data":{"n":1}}"#).unwrap();
+        assert_eq!(event.timestamp_ms, None);
+        assert_eq!(event.json().unwrap()["n"], 1);
+
+        let event = Event::parse(br#"{"topic":"custom"}"#).unwrap();
+        assert_eq!(event.data, "");
+    }
+
+    #[test]
+    fn rejects_malformed_envelopes() {
+        assert!(Event::parse(b"not json").is_err());
+        assert!(Event::parse(br#"{"data":"x"}"#).is_err());
+        assert!(Event::parse(&[0xff]).is_err());
+    }
+}
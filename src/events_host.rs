@@ -0,0 +1,39 @@
+//! `blockless_events` host module, enabled by the `events` feature.
+//!
+//! Every function returns 0 on success or a `codes::events` error code. Topics
+//! and payloads are passed as UTF-8 pointer/length pairs.
+
+#[link(wasm_import_module = "blockless_events")]
+extern "C" {
+    /// Subscribes to `topic` and writes the new subscription's handle to `handle`.
+    #[link_name = "events_subscribe"]
+    pub(crate) fn events_subscribe(topic: *const u8, topic_len: u32, handle: *mut u32) -> u32;
+
+    /// Waits up to `timeout_ms` for the next event on `handle` and writes it to
+    /// `buf` as a JSON envelope `{"topic": str, "timestamp": u64?, "data": any}`,
+    /// with its length in `num`. Returns `TIMED_OUT` if nothing arrived and
+    /// `CLOSED` once the host ends the subscription. If the event does not fit,
+    /// returns `BUFFER_TOO_SMALL` with the size it needs in `num` and keeps the
+    /// event queued for the next poll.
+    #[link_name = "events_poll"]
+    pub(crate) fn events_poll(
+        handle: u32,
+        timeout_ms: u32,
+        buf: *mut u8,
+        buf_len: u32,
+        num: *mut u32,
+    ) -> u32;
+
+    /// Publishes `data` on `topic` to every other subscriber.
+    #[link_name = "events_publish"]
+    pub(crate) fn events_publish(
+        topic: *const u8,
+        topic_len: u32,
+        data: *const u8,
+        data_len: u32,
+    ) -> u32;
+
+    /// Ends the subscription; `handle` is invalid afterwards.
+    #[link_name = "events_unsubscribe"]
+    pub(crate) fn events_unsubscribe(handle: u32) -> u32;
+}
@@ -0,0 +1,48 @@
+/// Wraps a host function call; with the `ffi-debug` feature enabled every call is
+/// logged to stderr with its module, function, payload size, return code and duration.
+///
+/// The payload expression is only evaluated when the feature is enabled, after the call.
+macro_rules! host_call {
+    ($module:literal, $func:expr, $call:expr) => {
+        host_call!($module, $func, &[], $call)
+    };
+    ($module:literal, $func:expr, $payload:expr, $call:expr) => {{
+        #[cfg(feature = "ffi-debug")]
+        let start = std::time::Instant::now();
+        let rs = $call;
+        #[cfg(feature = "ffi-debug")]
+        $crate::ffi_debug::log($module, $func, $payload, rs as i64, start.elapsed());
+        #[cfg(not(feature = "ffi-debug"))]
+        let _ = $func;
+        rs
+    }};
+}
+
+/// Payloads up to this many bytes are hex dumped along with the call.
+#[cfg(feature = "ffi-debug")]
+pub(crate) const HEX_DUMP_LIMIT: usize = 256;
+
+/// The bytes a host read reported as written, clamped to the buffer.
+#[cfg(feature = "ffi-debug")]
+pub(crate) fn written(buf: &[u8], num: u32) -> &[u8] {
+    &buf[..(num as usize).min(buf.len())]
+}
+
+#[cfg(feature = "ffi-debug")]
+pub(crate) fn log(module: &str, func: &str, payload: &[u8], rs: i64, elapsed: std::time::Duration) {
+    let mut line = format!(
+        "[ffi-debug] {}::{} payload={}B rs={} elapsed={}us",
+        module,
+        func,
+        payload.len(),
+        rs,
+        elapsed.as_micros()
+    );
+    if !payload.is_empty() && payload.len() <= HEX_DUMP_LIMIT {
+        line.push_str(" hex=");
+        payload
+            .iter()
+            .for_each(|b| line.push_str(&format!("{:02x}", b)));
+    }
+    eprintln!("{}", line);
+}
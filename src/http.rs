@@ -1,6 +1,25 @@
-use crate::{error::HttpErrorKind, http_host::*};
+use crate::{error::HttpErrorKind, http_host::*, retry::RetryPolicy, stream::ByteStream, Context};
 use json::JsonValue;
-use std::{cmp::Ordering, collections::BTreeMap};
+use std::{cmp::Ordering, collections::BTreeMap, time::Instant};
+use trace::HttpTrace;
+
+pub use multipart::MultipartPart;
+pub(crate) use policy::split_url;
+pub use policy::UrlPolicy;
+
+#[cfg(feature = "charset")]
+mod charset;
+#[cfg(feature = "decompress")]
+mod decompress;
+#[cfg(feature = "http-types")]
+mod interop;
+mod multipart;
+mod policy;
+pub mod server;
+#[cfg(feature = "signing")]
+pub mod signing;
+pub mod sse;
+pub mod trace;
 
 pub type Handle = u32;
 
@@ -9,14 +28,23 @@ pub type CodeStatus = u32;
 pub struct BlocklessHttp {
     inner: Handle,
     code: CodeStatus,
+    trace: HttpTrace,
 }
 
+#[derive(Debug, Clone)]
 pub struct HttpOptions {
     pub method: String,
     pub connect_timeout: u32,
     pub read_timeout: u32,
+    /// Millisecond precision timeouts; hosts that support them use these over
+    /// the whole-second fields.
+    pub connect_timeout_ms: Option<u32>,
+    pub read_timeout_ms: Option<u32>,
     pub body: Option<String>,
     pub headers: Option<BTreeMap<String, String>>,
+    /// `http://`, `https://` or `socks5://` proxy the host should route through;
+    /// hosts without proxy support ignore it.
+    pub proxy: Option<String>,
 }
 
 impl HttpOptions {
@@ -25,39 +53,118 @@ impl HttpOptions {
             method: method.into(),
             connect_timeout,
             read_timeout,
+            connect_timeout_ms: None,
+            read_timeout_ms: None,
             body: None,
             headers: None,
+            proxy: None,
         }
     }
 
+    /// Time allowed to establish the connection. Also rounds `connect_timeout`
+    /// up to whole seconds for hosts without millisecond support.
+    pub fn set_connect_timeout_ms(&mut self, ms: u32) {
+        self.connect_timeout_ms = Some(ms);
+        self.connect_timeout = ms.div_ceil(1000);
+    }
+
+    /// Time allowed between reads once connected; see `set_connect_timeout_ms`.
+    pub fn set_read_timeout_ms(&mut self, ms: u32) {
+        self.read_timeout_ms = Some(ms);
+        self.read_timeout = ms.div_ceil(1000);
+    }
+
+    /// Makes the request conditional on the resource having changed since the
+    /// response that carried `etag`; see `BlocklessHttp::is_not_modified`.
+    pub fn if_none_match(&mut self, etag: &str) {
+        self.set_header("If-None-Match", etag);
+    }
+
+    /// `timestamp` is an HTTP date, e.g. a previous response's `Last-Modified`.
+    pub fn if_modified_since(&mut self, timestamp: &str) {
+        self.set_header("If-Modified-Since", timestamp);
+    }
+
+    pub fn set_proxy(&mut self, url: &str) -> Result<(), HttpErrorKind> {
+        validate_proxy(url)?;
+        self.proxy = Some(url.to_string());
+        Ok(())
+    }
+
+    /// Sets `name`, replacing any existing header whose name differs only in case.
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        let headers = self.headers.get_or_insert_with(BTreeMap::new);
+        headers.retain(|k, _| !k.eq_ignore_ascii_case(name));
+        headers.insert(name.to_string(), value.to_string());
+    }
+
+    /// Advertises the encodings `BlocklessHttp::decompressed_body` can decode.
+    #[cfg(feature = "decompress")]
+    pub fn accept_encoding(&mut self) {
+        self.set_header("Accept-Encoding", decompress::ACCEPT_ENCODING);
+    }
+
     pub fn dump(&self) -> String {
-        // convert BTreeMap to json string
-        let mut headers_str = self
-            .headers
-            .clone()
-            .unwrap_or_default()
-            .iter()
-            .map(|(k, v)| format!("\"{}\":\"{}\"", k, v))
-            .collect::<Vec<String>>()
-            .join(",");
-        headers_str = format!("{{{}}}", headers_str);
+        // the host takes the headers as a JSON object encoded in a string
+        let mut headers = JsonValue::new_object();
+        for (name, value) in self.headers.iter().flatten() {
+            headers[name.as_str()] = value.as_str().into();
+        }
 
         let mut json = JsonValue::new_object();
         json["method"] = self.method.clone().into();
         json["connectTimeout"] = self.connect_timeout.into();
         json["readTimeout"] = self.read_timeout.into();
-        json["headers"] = headers_str.into();
+        if let Some(ms) = self.connect_timeout_ms {
+            json["connectTimeoutMs"] = ms.into();
+        }
+        if let Some(ms) = self.read_timeout_ms {
+            json["readTimeoutMs"] = ms.into();
+        }
+        json["headers"] = headers.dump().into();
         json["body"] = self.body.clone().into();
+        if let Some(proxy) = &self.proxy {
+            json["proxy"] = proxy.clone().into();
+        }
         json.dump()
     }
 }
 
+fn validate_proxy(url: &str) -> Result<(), HttpErrorKind> {
+    let (scheme, rest) = url.split_once("://").ok_or(HttpErrorKind::InvalidUrl)?;
+    let scheme = scheme.to_ascii_lowercase();
+    if !matches!(scheme.as_str(), "http" | "https" | "socks5") || rest.is_empty() {
+        return Err(HttpErrorKind::InvalidUrl);
+    }
+    Ok(())
+}
+
 impl BlocklessHttp {
+    /// Validates `opts` and encodes them for the host, starting the request's trace.
+    fn prepare(url: &str, opts: &HttpOptions) -> Result<(HttpTrace, String), HttpErrorKind> {
+        if let Some(proxy) = &opts.proxy {
+            validate_proxy(proxy)?;
+        }
+        let mut trace = HttpTrace::new(&opts.method, url);
+        let start = Instant::now();
+        let opts = match &trace.id {
+            Some(id) => {
+                let mut opts = opts.clone();
+                opts.set_header(trace::CORRELATION_HEADER, id);
+                opts.dump()
+            }
+            None => opts.dump(),
+        };
+        trace.serialize = start.elapsed();
+        Ok((trace, opts))
+    }
+
     pub fn open(url: &str, opts: &HttpOptions) -> Result<Self, HttpErrorKind> {
-        let opts = opts.dump();
+        let (mut trace, opts) = Self::prepare(url, opts)?;
+        let start = Instant::now();
         let mut fd = 0;
         let mut status = 0;
-        let rs = unsafe {
+        let rs = host_call!("blockless_http", "http_req", opts.as_bytes(), unsafe {
             http_open(
                 url.as_ptr(),
                 url.len() as _,
@@ -66,31 +173,164 @@ impl BlocklessHttp {
                 &mut fd,
                 &mut status,
             )
-        };
+        });
+        trace.open = start.elapsed();
         if rs != 0 {
+            trace::finish(&trace);
             return Err(HttpErrorKind::from(rs));
         }
         Ok(Self {
             inner: fd,
             code: status,
+            trace,
         })
     }
 
+    /// Like `open`, but streams the request body from `reader` in chunks instead
+    /// of sending `opts.body`, so large uploads are never held in memory whole.
+    #[cfg(feature = "http-upload")]
+    pub fn open_with_body_reader(
+        url: &str,
+        opts: &HttpOptions,
+        mut reader: impl std::io::Read,
+        content_length: Option<u64>,
+    ) -> Result<Self, HttpErrorKind> {
+        let mut opts = opts.clone();
+        opts.body = None;
+        if let Some(len) = content_length {
+            opts.set_header("Content-Length", &len.to_string());
+        }
+        let (mut trace, opts) = Self::prepare(url, &opts)?;
+        let start = Instant::now();
+        let mut fd = 0;
+        let rs = host_call!(
+            "blockless_http",
+            "http_req_begin",
+            opts.as_bytes(),
+            unsafe {
+                http_req_begin(
+                    url.as_ptr(),
+                    url.len() as _,
+                    opts.as_ptr(),
+                    opts.len() as _,
+                    &mut fd,
+                )
+            }
+        );
+        if rs != 0 {
+            trace.open = start.elapsed();
+            trace::finish(&trace);
+            return Err(HttpErrorKind::from(rs));
+        }
+        // from here on drop closes the handle if the upload fails
+        let mut http = Self {
+            inner: fd,
+            code: 0,
+            trace,
+        };
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => return Err(HttpErrorKind::BodyReadError),
+            };
+            let mut chunk = &buf[..n];
+            while !chunk.is_empty() {
+                let mut num: u32 = 0;
+                let rs = host_call!("blockless_http", "http_write_body", chunk, unsafe {
+                    http_write_body(fd, chunk.as_ptr(), chunk.len() as _, &mut num)
+                });
+                if rs != 0 {
+                    return Err(HttpErrorKind::from(rs));
+                }
+                if num == 0 {
+                    return Err(HttpErrorKind::RequestError);
+                }
+                chunk = &chunk[num as usize..];
+            }
+        }
+        let rs = host_call!("blockless_http", "http_req_finish", unsafe {
+            http_req_finish(fd, &mut http.code)
+        });
+        http.trace.open = start.elapsed();
+        if rs != 0 {
+            return Err(HttpErrorKind::from(rs));
+        }
+        Ok(http)
+    }
+
+    pub fn open_with_retry(
+        url: &str,
+        opts: &HttpOptions,
+        policy: &RetryPolicy,
+    ) -> Result<Self, HttpErrorKind> {
+        policy.run(|_| Self::open(url, opts))
+    }
+
+    /// Lets `signer` add its authentication headers, then opens the request.
+    #[cfg(feature = "signing")]
+    pub fn open_signed(
+        url: &str,
+        opts: &HttpOptions,
+        signer: &dyn signing::RequestSigner,
+    ) -> Result<Self, HttpErrorKind> {
+        let mut opts = opts.clone();
+        signer.sign(url, &mut opts)?;
+        Self::open(url, &opts)
+    }
+
+    /// Opens the request with its timeouts capped to the context's remaining time.
+    pub fn open_with_context(
+        url: &str,
+        opts: &HttpOptions,
+        ctx: &Context,
+    ) -> Result<Self, HttpErrorKind> {
+        ctx.check()?;
+        let mut opts = opts.clone();
+        if let Some(remaining) = ctx.remaining() {
+            let secs = remaining.as_secs_f64().ceil().max(1.0) as u32;
+            let ms = remaining.as_millis().clamp(1, u32::MAX as u128) as u32;
+            opts.connect_timeout = opts.connect_timeout.min(secs);
+            opts.read_timeout = opts.read_timeout.min(secs);
+            opts.connect_timeout_ms = Some(opts.connect_timeout_ms.map_or(ms, |t| t.min(ms)));
+            opts.read_timeout_ms = Some(opts.read_timeout_ms.map_or(ms, |t| t.min(ms)));
+        }
+        let http = Self::open(url, &opts)?;
+        ctx.check()?;
+        Ok(http)
+    }
+
     pub fn get_code(&self) -> CodeStatus {
         self.code
     }
 
+    pub fn trace(&self) -> &HttpTrace {
+        &self.trace
+    }
+
+    /// True for a 304 answer to a conditional request; the body is empty and
+    /// the previously fetched copy is still current.
+    pub fn is_not_modified(&self) -> bool {
+        self.code == 304
+    }
+
+    /// The `ETag` validator to send back with `HttpOptions::if_none_match`.
+    pub fn etag(&self) -> Option<String> {
+        self.get_header("etag").ok()
+    }
+
+    /// The `Last-Modified` validator to send back with `HttpOptions::if_modified_since`.
+    pub fn last_modified(&self) -> Option<String> {
+        self.get_header("last-modified").ok()
+    }
+
     pub fn get_all_body(&self) -> Result<Vec<u8>, HttpErrorKind> {
         let mut vec = Vec::new();
         loop {
             let mut buf = [0u8; 1024];
-            let mut num: u32 = 0;
-            let rs =
-                unsafe { http_read_body(self.inner, buf.as_mut_ptr(), buf.len() as _, &mut num) };
-            if rs != 0 {
-                return Err(HttpErrorKind::from(rs));
-            }
-
+            let num = self.read_body(&mut buf)?;
             match num.cmp(&0) {
                 Ordering::Greater => vec.extend_from_slice(&buf[0..num as _]),
                 _ => break,
@@ -99,21 +339,136 @@ impl BlocklessHttp {
         Ok(vec)
     }
 
+    pub fn get_all_body_with_context(&self, ctx: &Context) -> Result<Vec<u8>, HttpErrorKind> {
+        let mut vec = Vec::new();
+        let mut buf = [0u8; 1024];
+        loop {
+            ctx.check()?;
+            match self.read_body(&mut buf)? {
+                0 => break,
+                num => vec.extend_from_slice(&buf[0..num as _]),
+            }
+        }
+        Ok(vec)
+    }
+
+    /// Reads the whole body, failing with `ResponseTooLarge` once more than
+    /// `max_bytes` arrive. `on_progress` gets the bytes read so far and the
+    /// `Content-Length`, when the server sent one, after every chunk.
+    pub fn download(
+        &self,
+        max_bytes: usize,
+        mut on_progress: impl FnMut(usize, Option<usize>),
+    ) -> Result<Vec<u8>, HttpErrorKind> {
+        let total = self
+            .get_header("content-length")
+            .ok()
+            .and_then(|len| len.trim().parse::<usize>().ok());
+        if total.is_some_and(|total| total > max_bytes) {
+            return Err(HttpErrorKind::ResponseTooLarge);
+        }
+        let mut vec = Vec::with_capacity(total.unwrap_or(0));
+        let mut buf = [0u8; 4096];
+        loop {
+            match self.read_body(&mut buf)? {
+                0 => break,
+                num => {
+                    if vec.len() + num as usize > max_bytes {
+                        return Err(HttpErrorKind::ResponseTooLarge);
+                    }
+                    vec.extend_from_slice(&buf[0..num as _]);
+                    on_progress(vec.len(), total);
+                }
+            }
+        }
+        Ok(vec)
+    }
+
+    /// Reads the body as text. With the `charset` feature the encoding comes
+    /// from the `Content-Type` charset or an HTML `<meta>` tag; without it the
+    /// body must be UTF-8.
+    pub fn text(&self) -> Result<String, HttpErrorKind> {
+        self.decode_text(false)
+    }
+
+    /// Like `text`, replacing undecodable bytes with U+FFFD instead of failing.
+    pub fn text_lossy(&self) -> Result<String, HttpErrorKind> {
+        self.decode_text(true)
+    }
+
+    /// Decodes the body as `label` (e.g. `shift_jis`), ignoring what the response declares.
+    #[cfg(feature = "charset")]
+    pub fn text_with_charset(&self, label: &str) -> Result<String, HttpErrorKind> {
+        let encoding = charset::for_label(label)?;
+        charset::decode(encoding, &self.get_all_body()?, false)
+    }
+
+    #[cfg(feature = "charset")]
+    fn decode_text(&self, lossy: bool) -> Result<String, HttpErrorKind> {
+        let content_type = match self.get_header("content-type") {
+            Ok(content_type) => Some(content_type),
+            Err(HttpErrorKind::HeaderNotFound) => None,
+            Err(e) => return Err(e),
+        };
+        let body = self.get_all_body()?;
+        let encoding = charset::detect(content_type.as_deref(), &body);
+        charset::decode(encoding, &body, lossy)
+    }
+
+    #[cfg(not(feature = "charset"))]
+    fn decode_text(&self, lossy: bool) -> Result<String, HttpErrorKind> {
+        let body = self.get_all_body()?;
+        if lossy {
+            return Ok(String::from_utf8_lossy(&body).into_owned());
+        }
+        String::from_utf8(body).map_err(|_| HttpErrorKind::Utf8Error)
+    }
+
+    /// Reads the body and splits a `multipart/*` response into its parts.
+    pub fn multipart(&self) -> Result<Vec<MultipartPart>, HttpErrorKind> {
+        let content_type = self.get_header("content-type")?;
+        multipart::parse(&content_type, &self.get_all_body()?)
+    }
+
+    /// Reads the whole body and undoes its `Content-Encoding` (gzip, deflate, br).
+    #[cfg(feature = "decompress")]
+    pub fn decompressed_body(&self) -> Result<Vec<u8>, HttpErrorKind> {
+        let body = self.get_all_body()?;
+        match self.get_header("content-encoding") {
+            Ok(encoding) => decompress::decompress(&encoding, body),
+            Err(HttpErrorKind::HeaderNotFound) => Ok(body),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Streams the body in chunks instead of buffering it whole.
+    pub fn body_stream(&self) -> HttpBodyStream<'_> {
+        HttpBodyStream {
+            http: self,
+            done: false,
+        }
+    }
+
     pub fn get_header(&self, header: &str) -> Result<String, HttpErrorKind> {
         let mut vec = Vec::new();
         loop {
             let mut buf = [0u8; 1024];
             let mut num: u32 = 0;
-            let rs = unsafe {
-                http_read_header(
-                    self.inner,
-                    header.as_ptr(),
-                    header.len() as _,
-                    buf.as_mut_ptr(),
-                    buf.len() as _,
-                    &mut num,
-                )
-            };
+            let rs = host_call!(
+                "blockless_http",
+                "http_read_header",
+                crate::ffi_debug::written(&buf, num),
+                unsafe {
+                    http_read_header(
+                        self.inner,
+                        header.as_ptr(),
+                        header.len() as _,
+                        buf.as_mut_ptr(),
+                        buf.len() as _,
+                        &mut num,
+                    )
+                }
+            );
             if rs != 0 {
                 return Err(HttpErrorKind::from(rs));
             }
@@ -126,14 +481,21 @@ impl BlocklessHttp {
     }
 
     pub fn close(self) {
-        unsafe {
-            http_close(self.inner);
-        }
+        host_call!("blockless_http", "http_close", unsafe {
+            http_close(self.inner)
+        });
     }
 
     pub fn read_body(&self, buf: &mut [u8]) -> Result<u32, HttpErrorKind> {
+        let start = Instant::now();
         let mut num: u32 = 0;
-        let rs = unsafe { http_read_body(self.inner, buf.as_mut_ptr(), buf.len() as _, &mut num) };
+        let rs = host_call!(
+            "blockless_http",
+            "http_read_body",
+            crate::ffi_debug::written(buf, num),
+            unsafe { http_read_body(self.inner, buf.as_mut_ptr(), buf.len() as _, &mut num) }
+        );
+        self.trace.add_body(start.elapsed());
         if rs != 0 {
             return Err(HttpErrorKind::from(rs));
         }
@@ -141,10 +503,75 @@ impl BlocklessHttp {
     }
 }
 
+pub struct HttpBodyStream<'a> {
+    http: &'a BlocklessHttp,
+    done: bool,
+}
+
+impl ByteStream for HttpBodyStream<'_> {
+    type Error = HttpErrorKind;
+
+    fn next(&mut self) -> Option<Result<Vec<u8>, HttpErrorKind>> {
+        if self.done {
+            return None;
+        }
+        let mut buf = [0u8; 1024];
+        match self.http.read_body(&mut buf) {
+            Ok(0) => {
+                self.done = true;
+                None
+            }
+            Ok(num) => Some(Ok(buf[0..num as _].to_vec())),
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
 impl Drop for BlocklessHttp {
     fn drop(&mut self) {
-        unsafe {
-            http_close(self.inner);
-        }
+        host_call!("blockless_http", "http_close", unsafe {
+            http_close(self.inner)
+        });
+        trace::finish(&self.trace);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dumped_headers(opts: &HttpOptions) -> JsonValue {
+        let json = json::parse(&opts.dump()).unwrap();
+        json::parse(json["headers"].as_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn quoted_etags_survive_dump() {
+        let mut opts = HttpOptions::new("GET", 30, 30);
+        opts.if_none_match("\"33a64df5\"");
+        assert_eq!(dumped_headers(&opts)["If-None-Match"], "\"33a64df5\"");
+
+        opts.if_none_match("W/\"x\\y\"");
+        assert_eq!(dumped_headers(&opts)["If-None-Match"], "W/\"x\\y\"");
+    }
+
+    #[test]
+    fn set_header_replaces_names_case_insensitively() {
+        let mut opts = HttpOptions::new("PUT", 30, 30);
+        opts.set_header("content-length", "3");
+        opts.set_header("Accept", "*/*");
+        opts.set_header("Content-Length", "10");
+        let headers = opts.headers.unwrap();
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers["Content-Length"], "10");
+    }
+
+    #[test]
+    fn dump_without_headers_sends_an_empty_object() {
+        let opts = HttpOptions::new("GET", 30, 30);
+        assert_eq!(dumped_headers(&opts), JsonValue::new_object());
     }
 }
@@ -0,0 +1,114 @@
+use encoding_rs::Encoding;
+
+use crate::HttpErrorKind;
+
+/// Picks the body's encoding: a byte order mark wins, then the `Content-Type`
+/// charset, then a `<meta>` declaration near the top of an HTML body, then UTF-8.
+pub(crate) fn detect(content_type: Option<&str>, body: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(body) {
+        return encoding;
+    }
+    content_type
+        .and_then(charset_param)
+        .or_else(|| meta_charset(body))
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8)
+}
+
+pub(crate) fn decode(
+    encoding: &'static Encoding,
+    body: &[u8],
+    lossy: bool,
+) -> Result<String, HttpErrorKind> {
+    let (text, _, had_errors) = encoding.decode(body);
+    if had_errors && !lossy {
+        return Err(HttpErrorKind::InvalidEncoding);
+    }
+    Ok(text.into_owned())
+}
+
+pub(crate) fn for_label(label: &str) -> Result<&'static Encoding, HttpErrorKind> {
+    Encoding::for_label(label.trim().as_bytes()).ok_or(HttpErrorKind::InvalidEncoding)
+}
+
+fn charset_param(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|p| {
+        let (k, v) = p.split_once('=')?;
+        k.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| v.trim().trim_matches('"').to_string())
+    })
+}
+
+/// `<meta charset="...">` or `<meta http-equiv="Content-Type" content="...; charset=...">`
+/// within the first 1024 bytes, as browsers prescan.
+fn meta_charset(body: &[u8]) -> Option<String> {
+    let head = String::from_utf8_lossy(&body[..body.len().min(1024)]).to_ascii_lowercase();
+    let mut rest = head.as_str();
+    while let Some(i) = rest.find("<meta") {
+        rest = &rest[i + 5..];
+        let tag = &rest[..rest.find('>').unwrap_or(rest.len())];
+        if let Some(j) = tag.find("charset=") {
+            let value = tag[j + 8..].trim_start_matches(['"', '\'', ' ']);
+            let end = value
+                .find(|c: char| c == '"' || c == '\'' || c == ';' || c == '/' || c.is_whitespace())
+                .unwrap_or(value.len());
+            if end > 0 {
+                return Some(value[..end].to_string());
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bom_wins_over_content_type() {
+        let body = b"\xEF\xBB\xBFhi";
+        assert_eq!(
+            detect(Some("text/plain; charset=latin1"), body),
+            encoding_rs::UTF_8
+        );
+    }
+
+    #[test]
+    fn content_type_charset_is_used() {
+        let enc = detect(Some("text/html; Charset=\"ISO-8859-1\""), b"");
+        assert_eq!(enc, encoding_rs::WINDOWS_1252);
+        assert_eq!(decode(enc, b"caf\xE9", false).unwrap(), "caf\u{e9}");
+    }
+
+    #[test]
+    fn meta_charset_is_sniffed() {
+        let body = b"<html><head><meta charset='shift_jis'></head>";
+        assert_eq!(detect(Some("text/html"), body), encoding_rs::SHIFT_JIS);
+        let body = br#"<meta http-equiv="Content-Type" content="text/html; charset=koi8-r">"#;
+        assert_eq!(detect(None, body), encoding_rs::KOI8_R);
+    }
+
+    #[test]
+    fn defaults_to_utf8() {
+        assert_eq!(detect(None, b"plain"), encoding_rs::UTF_8);
+        assert_eq!(
+            detect(Some("text/plain; charset=bogus"), b""),
+            encoding_rs::UTF_8
+        );
+    }
+
+    #[test]
+    fn invalid_bytes_fail_unless_lossy() {
+        assert!(matches!(
+            decode(encoding_rs::UTF_8, b"\xFF", false),
+            Err(HttpErrorKind::InvalidEncoding)
+        ));
+        assert_eq!(
+            decode(encoding_rs::UTF_8, b"\xFF", true).unwrap(),
+            "\u{fffd}"
+        );
+        assert!(for_label("nope").is_err());
+        assert_eq!(for_label(" utf-8 ").unwrap(), encoding_rs::UTF_8);
+    }
+}
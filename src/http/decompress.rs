@@ -0,0 +1,106 @@
+use std::io::Read;
+
+use crate::HttpErrorKind;
+
+pub(crate) const ACCEPT_ENCODING: &str = "gzip, deflate, br";
+
+/// Decodes `body` according to a `Content-Encoding` header value. Multiple
+/// codings are undone in reverse order of application.
+pub(crate) fn decompress(encoding: &str, body: Vec<u8>) -> Result<Vec<u8>, HttpErrorKind> {
+    encoding
+        .split(',')
+        .map(|coding| coding.trim().to_ascii_lowercase())
+        .filter(|coding| !coding.is_empty())
+        .rev()
+        .try_fold(body, |body, coding| decode(&coding, body))
+}
+
+fn decode(coding: &str, body: Vec<u8>) -> Result<Vec<u8>, HttpErrorKind> {
+    let mut out = Vec::new();
+    let rs = match coding {
+        "identity" => return Ok(body),
+        "gzip" | "x-gzip" => flate2::read::MultiGzDecoder::new(&body[..]).read_to_end(&mut out),
+        "deflate" => read_deflate(&body, &mut out),
+        "br" => brotli_decompressor::Decompressor::new(&body[..], 4096).read_to_end(&mut out),
+        _ => return Err(HttpErrorKind::DecompressionError),
+    };
+    rs.map_err(|_| HttpErrorKind::DecompressionError)?;
+    Ok(out)
+}
+
+// "deflate" is meant to be zlib wrapped, but some servers send raw deflate.
+fn read_deflate(body: &[u8], out: &mut Vec<u8>) -> std::io::Result<usize> {
+    match flate2::read::ZlibDecoder::new(body).read_to_end(out) {
+        Ok(n) => Ok(n),
+        Err(_) => {
+            out.clear();
+            flate2::read::DeflateDecoder::new(body).read_to_end(out)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{
+        write::{DeflateEncoder, GzEncoder, ZlibEncoder},
+        Compression,
+    };
+
+    use super::*;
+
+    const TEXT: &[u8] = b"hello, hello, hello";
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut w = GzEncoder::new(Vec::new(), Compression::default());
+        w.write_all(data).unwrap();
+        w.finish().unwrap()
+    }
+
+    #[test]
+    fn gzip_and_zlib_deflate() {
+        assert_eq!(decompress("gzip", gzip(TEXT)).unwrap(), TEXT);
+        assert_eq!(decompress("X-Gzip", gzip(TEXT)).unwrap(), TEXT);
+
+        let mut w = ZlibEncoder::new(Vec::new(), Compression::default());
+        w.write_all(TEXT).unwrap();
+        assert_eq!(decompress("deflate", w.finish().unwrap()).unwrap(), TEXT);
+    }
+
+    #[test]
+    fn raw_deflate_falls_back() {
+        let mut w = DeflateEncoder::new(Vec::new(), Compression::default());
+        w.write_all(TEXT).unwrap();
+        assert_eq!(decompress("deflate", w.finish().unwrap()).unwrap(), TEXT);
+    }
+
+    #[test]
+    fn brotli() {
+        // "hello, brotli" as produced by the reference encoder.
+        let body = vec![
+            11, 6, 128, 104, 101, 108, 108, 111, 44, 32, 98, 114, 111, 116, 108, 105, 3,
+        ];
+        assert_eq!(decompress("br", body).unwrap(), b"hello, brotli");
+    }
+
+    #[test]
+    fn stacked_codings_are_undone_in_reverse() {
+        let twice = gzip(&gzip(TEXT));
+        assert_eq!(decompress("gzip, gzip", twice).unwrap(), TEXT);
+        assert_eq!(decompress("identity, gzip", gzip(TEXT)).unwrap(), TEXT);
+        assert_eq!(decompress("", TEXT.to_vec()).unwrap(), TEXT);
+    }
+
+    #[test]
+    fn unknown_or_corrupt_codings_fail() {
+        assert!(matches!(
+            decompress("compress", TEXT.to_vec()),
+            Err(HttpErrorKind::DecompressionError)
+        ));
+        assert!(matches!(
+            decompress("gzip", TEXT.to_vec()),
+            Err(HttpErrorKind::DecompressionError)
+        ));
+    }
+}
@@ -0,0 +1,156 @@
+//! Conversions to and from the `http` crate's types, so code written against
+//! `http::Request`/`http::Response` can run on the host's http module.
+
+use ::http::{header::HeaderName, HeaderMap, HeaderValue, Request, Response, StatusCode};
+
+use crate::{BlocklessHttp, HttpErrorKind, HttpOptions};
+
+impl HttpOptions {
+    /// Builds options from a request's method, headers and body; the url is
+    /// `req.uri()`. Repeated headers are joined with `, `.
+    pub fn from_request(
+        req: &Request<Vec<u8>>,
+        connect_timeout: u32,
+        read_timeout: u32,
+    ) -> Result<Self, HttpErrorKind> {
+        let mut opts = HttpOptions::new(req.method().as_str(), connect_timeout, read_timeout);
+        for (name, value) in req.headers() {
+            let value = value.to_str().map_err(|_| HttpErrorKind::InvalidEncoding)?;
+            let headers = opts.headers.get_or_insert_with(Default::default);
+            headers
+                .entry(name.as_str().to_string())
+                .and_modify(|v| {
+                    v.push_str(", ");
+                    v.push_str(value);
+                })
+                .or_insert_with(|| value.to_string());
+        }
+        if !req.body().is_empty() {
+            let body =
+                String::from_utf8(req.body().clone()).map_err(|_| HttpErrorKind::Utf8Error)?;
+            opts.body = Some(body);
+        }
+        Ok(opts)
+    }
+
+    /// The headers as a `HeaderMap`; entries `http` considers invalid are skipped.
+    pub fn header_map(&self) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in self.headers.iter().flatten() {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                map.append(name, value);
+            }
+        }
+        map
+    }
+}
+
+impl BlocklessHttp {
+    /// Sends `req`. With the `http-upload` feature a body that is not UTF-8 is
+    /// streamed with `open_with_body_reader`; without it such a body is a `Utf8Error`.
+    pub fn open_request(
+        req: &Request<Vec<u8>>,
+        connect_timeout: u32,
+        read_timeout: u32,
+    ) -> Result<Self, HttpErrorKind> {
+        let url = req.uri().to_string();
+        match HttpOptions::from_request(req, connect_timeout, read_timeout) {
+            Ok(opts) => Self::open(&url, &opts),
+            #[cfg(feature = "http-upload")]
+            Err(HttpErrorKind::Utf8Error) => {
+                let mut binary = req.clone();
+                binary.body_mut().clear();
+                let opts = HttpOptions::from_request(&binary, connect_timeout, read_timeout)?;
+                let body = req.body().as_slice();
+                Self::open_with_body_reader(&url, &opts, body, Some(body.len() as u64))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn status_code(&self) -> Option<StatusCode> {
+        StatusCode::from_u16(self.get_code() as u16).ok()
+    }
+
+    /// The host only looks headers up by name, so the ones to copy are listed;
+    /// headers the response does not carry are left out.
+    pub fn header_map(&self, names: &[&str]) -> Result<HeaderMap, HttpErrorKind> {
+        let mut map = HeaderMap::new();
+        for name in names {
+            let value = match self.get_header(name) {
+                Ok(value) => value,
+                Err(HttpErrorKind::HeaderNotFound) => continue,
+                Err(e) => return Err(e),
+            };
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| HttpErrorKind::InvalidEncoding)?;
+            let value =
+                HeaderValue::from_str(&value).map_err(|_| HttpErrorKind::InvalidEncoding)?;
+            map.append(name, value);
+        }
+        Ok(map)
+    }
+
+    /// Reads the body into an `http::Response` carrying the listed headers.
+    pub fn into_response(self, header_names: &[&str]) -> Result<Response<Vec<u8>>, HttpErrorKind> {
+        let status = self.status_code().ok_or(HttpErrorKind::RequestError)?;
+        let headers = self.header_map(header_names)?;
+        let mut response = Response::new(self.get_all_body()?);
+        *response.status_mut() = status;
+        *response.headers_mut() = headers;
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_request_joins_repeated_headers() {
+        let req = Request::builder()
+            .method("POST")
+            .uri("https://example.com/")
+            .header("Accept", "text/html")
+            .header("accept", "application/json")
+            .header("X-One", "1")
+            .body(b"body".to_vec())
+            .unwrap();
+        let opts = HttpOptions::from_request(&req, 10, 20).unwrap();
+        assert_eq!(opts.method, "POST");
+        assert_eq!((opts.connect_timeout, opts.read_timeout), (10, 20));
+        let headers = opts.headers.as_ref().unwrap();
+        assert_eq!(headers["accept"], "text/html, application/json");
+        assert_eq!(headers["x-one"], "1");
+        assert_eq!(opts.body.as_deref(), Some("body"));
+
+        let map = opts.header_map();
+        assert_eq!(map["accept"], "text/html, application/json");
+    }
+
+    #[test]
+    fn from_request_rejects_non_utf8_bodies() {
+        let req = Request::builder()
+            .uri("https://example.com/")
+            .body(vec![0xff, 0xfe])
+            .unwrap();
+        assert!(matches!(
+            HttpOptions::from_request(&req, 10, 20),
+            Err(HttpErrorKind::Utf8Error)
+        ));
+    }
+
+    #[test]
+    fn empty_bodies_stay_unset() {
+        let req = Request::builder()
+            .uri("https://example.com/")
+            .body(Vec::new())
+            .unwrap();
+        let opts = HttpOptions::from_request(&req, 10, 20).unwrap();
+        assert_eq!(opts.method, "GET");
+        assert_eq!(opts.body, None);
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+
+use crate::HttpErrorKind;
+
+/// One part of a `multipart/*` body.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultipartPart {
+    /// Header names are lowercased.
+    pub headers: BTreeMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl MultipartPart {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.header("content-type")
+    }
+
+    /// The form field name from `Content-Disposition`.
+    pub fn name(&self) -> Option<String> {
+        param(self.header("content-disposition")?, "name")
+    }
+
+    pub fn filename(&self) -> Option<String> {
+        param(self.header("content-disposition")?, "filename")
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.body).ok()
+    }
+}
+
+/// Splits `body` on the boundary named in `content_type`. Accepts bare `\n`
+/// line endings as well as `\r\n`.
+pub(crate) fn parse(content_type: &str, body: &[u8]) -> Result<Vec<MultipartPart>, HttpErrorKind> {
+    if !content_type
+        .trim_start()
+        .to_ascii_lowercase()
+        .starts_with("multipart/")
+    {
+        return Err(HttpErrorKind::InvalidMultipart);
+    }
+    let boundary = param(content_type, "boundary").ok_or(HttpErrorKind::InvalidMultipart)?;
+    let delim = format!("--{}", boundary).into_bytes();
+    let mut pos = if body.starts_with(&delim) {
+        0
+    } else {
+        find_delimiter(body, &delim, 0).ok_or(HttpErrorKind::InvalidMultipart)?
+    };
+    let mut parts = Vec::new();
+    loop {
+        pos += delim.len();
+        if body[pos..].starts_with(b"--") {
+            return Ok(parts);
+        }
+        // skip transport padding up to the end of the delimiter line
+        let start = find(body, b"\n", pos).ok_or(HttpErrorKind::InvalidMultipart)? + 1;
+        let next = find_delimiter(body, &delim, start).ok_or(HttpErrorKind::InvalidMultipart)?;
+        let mut end = (next - 1).max(start);
+        if end > start && body[end - 1] == b'\r' {
+            end -= 1;
+        }
+        parts.push(parse_part(&body[start..end]));
+        pos = next;
+    }
+}
+
+fn parse_part(part: &[u8]) -> MultipartPart {
+    let (head, body) = if part.starts_with(b"\r\n") {
+        (&part[..0], &part[2..])
+    } else if part.starts_with(b"\n") {
+        (&part[..0], &part[1..])
+    } else if let Some(i) = find(part, b"\r\n\r\n", 0) {
+        (&part[..i], &part[i + 4..])
+    } else if let Some(i) = find(part, b"\n\n", 0) {
+        (&part[..i], &part[i + 2..])
+    } else {
+        (part, &part[part.len()..])
+    };
+    let mut headers: BTreeMap<String, String> = BTreeMap::new();
+    let mut last: Option<String> = None;
+    for line in String::from_utf8_lossy(head).lines() {
+        let line = line.trim_end_matches('\r');
+        if line.starts_with([' ', '\t']) {
+            // folded continuation of the previous header
+            if let Some(value) = last.as_ref().and_then(|name| headers.get_mut(name)) {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let name = name.trim().to_ascii_lowercase();
+            headers.insert(name.clone(), value.trim().to_string());
+            last = Some(name);
+        }
+    }
+    MultipartPart {
+        headers,
+        body: body.to_vec(),
+    }
+}
+
+/// Position of a delimiter that starts a line at or after `from`.
+fn find_delimiter(body: &[u8], delim: &[u8], from: usize) -> Option<usize> {
+    let mut needle = Vec::with_capacity(delim.len() + 1);
+    needle.push(b'\n');
+    needle.extend_from_slice(delim);
+    find(body, &needle, from.saturating_sub(1)).map(|i| i + 1)
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|i| i + from)
+}
+
+/// A `key=value` or `key="value"` parameter of a header value.
+fn param(value: &str, key: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|p| {
+        let (k, v) = p.split_once('=')?;
+        if !k.trim().eq_ignore_ascii_case(key) {
+            return None;
+        }
+        let v = v.trim();
+        Some(
+            match v.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                Some(quoted) => quoted.replace("\\\"", "\""),
+                None => v.to_string(),
+            },
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORM: &[u8] = b"preamble\r\n--XyZ\r\n\
+Content-Disposition: form-data; name=\"field\"\r\n\
+\r\n\
+value\r\n\
+--XyZ\r\n\
+Content-Disposition: form-data; name=\"file\";\r\n filename=\"a \\\"b\\\".txt\"\r\n\
+Content-Type: text/plain\r\n\
+\r\n\
+line1\r\nline2\r\n\
+--XyZ--\r\nepilogue";
+
+    #[test]
+    fn parses_form_parts() {
+        let parts = parse("multipart/form-data; boundary=XyZ", FORM).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name().as_deref(), Some("field"));
+        assert_eq!(parts[0].text(), Some("value"));
+        assert_eq!(parts[0].content_type(), None);
+        assert_eq!(parts[1].name().as_deref(), Some("file"));
+        assert_eq!(parts[1].filename().as_deref(), Some("a \"b\".txt"));
+        assert_eq!(parts[1].header("Content-Type"), Some("text/plain"));
+        assert_eq!(parts[1].body, b"line1\r\nline2");
+    }
+
+    #[test]
+    fn accepts_bare_newlines_and_quoted_boundary() {
+        let body = b"--b\nContent-Type: text/plain\n\nhello\n--b\n\nno headers\n--b--\n";
+        let parts = parse("multipart/mixed; boundary=\"b\"", body).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].text(), Some("hello"));
+        assert!(parts[1].headers.is_empty());
+        assert_eq!(parts[1].text(), Some("no headers"));
+    }
+
+    #[test]
+    fn boundary_inside_a_line_is_not_a_delimiter() {
+        let body = b"--b\r\n\r\nx--b y\r\n--b--";
+        let parts = parse("multipart/mixed; boundary=b", body).unwrap();
+        assert_eq!(parts[0].text(), Some("x--b y"));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(parse("text/plain; boundary=b", b"--b--").is_err());
+        assert!(parse("multipart/mixed", b"--b--").is_err());
+        assert!(parse("multipart/mixed; boundary=b", b"no delimiter").is_err());
+        assert!(parse("multipart/mixed; boundary=b", b"--b\r\n\r\nunterminated").is_err());
+    }
+}
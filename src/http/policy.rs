@@ -0,0 +1,272 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::HttpErrorKind;
+
+/// Client-side restrictions on what an app may fetch, checked before a request
+/// reaches the host. Deny rules win over allow rules; with no allow rules every
+/// domain not denied is allowed.
+///
+/// Domain rules are globs where `*` matches any run of characters, so
+/// `*.example.com` covers subdomains but not `example.com` itself. Hostnames
+/// are not resolved, so private-IP blocking only sees IP literals (including
+/// shorthand IPv4 such as `127.1`, `2130706433` or `0x7f.0.0.1`) and `localhost`.
+///
+/// The policy is enforced for requests made through a `Session` with
+/// `Session::set_url_policy`. `BlocklessHttp::open` does not consult it; call
+/// [`UrlPolicy::check`] first when opening requests directly.
+#[derive(Debug, Clone)]
+pub struct UrlPolicy {
+    allow_domains: Vec<String>,
+    deny_domains: Vec<String>,
+    schemes: Vec<String>,
+    block_private_ips: bool,
+}
+
+impl Default for UrlPolicy {
+    fn default() -> Self {
+        UrlPolicy {
+            allow_domains: Vec::new(),
+            deny_domains: Vec::new(),
+            schemes: vec!["http".to_string(), "https".to_string()],
+            block_private_ips: false,
+        }
+    }
+}
+
+impl UrlPolicy {
+    /// Allows http and https to any domain.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn allow_domain(mut self, glob: &str) -> Self {
+        self.allow_domains.push(glob.to_ascii_lowercase());
+        self
+    }
+
+    pub fn deny_domain(mut self, glob: &str) -> Self {
+        self.deny_domains.push(glob.to_ascii_lowercase());
+        self
+    }
+
+    /// Replaces the allowed schemes.
+    pub fn schemes(mut self, schemes: &[&str]) -> Self {
+        self.schemes = schemes.iter().map(|s| s.to_ascii_lowercase()).collect();
+        self
+    }
+
+    pub fn block_private_ips(mut self, block: bool) -> Self {
+        self.block_private_ips = block;
+        self
+    }
+
+    pub fn check(&self, url: &str) -> Result<(), HttpErrorKind> {
+        let (scheme, host) = split_url(url).ok_or(HttpErrorKind::InvalidUrl)?;
+        if !self.schemes.contains(&scheme) {
+            return Err(HttpErrorKind::BlockedByPolicy);
+        }
+        if self.deny_domains.iter().any(|glob| glob_match(glob, &host)) {
+            return Err(HttpErrorKind::BlockedByPolicy);
+        }
+        if !self.allow_domains.is_empty()
+            && !self
+                .allow_domains
+                .iter()
+                .any(|glob| glob_match(glob, &host))
+        {
+            return Err(HttpErrorKind::BlockedByPolicy);
+        }
+        if self.block_private_ips && is_private_host(&host) {
+            return Err(HttpErrorKind::BlockedByPolicy);
+        }
+        Ok(())
+    }
+}
+
+/// Lowercased scheme and host (without brackets, port or userinfo).
+pub(crate) fn split_url(url: &str) -> Option<(String, String)> {
+    let (scheme, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let host = match host_port.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or(""),
+        None => host_port.split(':').next().unwrap_or(""),
+    };
+    if scheme.is_empty() || host.is_empty() {
+        return None;
+    }
+    let host = host.trim_end_matches('.');
+    Some((scheme.to_ascii_lowercase(), host.to_ascii_lowercase()))
+}
+
+fn glob_match(glob: &str, text: &str) -> bool {
+    let Some((prefix, rest)) = glob.split_once('*') else {
+        return glob == text;
+    };
+    let Some(mut text) = text.strip_prefix(prefix) else {
+        return false;
+    };
+    let mut parts = rest.split('*').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return text.ends_with(part);
+        }
+        match text.find(part) {
+            Some(i) => text = &text[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+fn is_private_host(host: &str) -> bool {
+    if host == "localhost" || host.ends_with(".localhost") {
+        return true;
+    }
+    if let Some(ip) = parse_ipv4(host) {
+        return is_private_v4(ip);
+    }
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => is_private_v4(ip),
+        Ok(IpAddr::V6(ip)) => is_private_v6(ip),
+        Err(_) => false,
+    }
+}
+
+/// IPv4 the way URL parsers and resolvers read it: one to four dot-separated
+/// parts, each decimal, octal (leading `0`) or hex (`0x`), the last part
+/// filling the remaining bytes. `None` if `host` is not such an address.
+fn parse_ipv4(host: &str) -> Option<Ipv4Addr> {
+    let parts = host.split('.').collect::<Vec<_>>();
+    if parts.len() > 4 {
+        return None;
+    }
+    let numbers = parts
+        .iter()
+        .map(|part| parse_ipv4_number(part))
+        .collect::<Option<Vec<u64>>>()?;
+    let (last, init) = numbers.split_last()?;
+    if init.iter().any(|&n| n > 255) || *last >= 1u64 << (8 * (5 - numbers.len())) {
+        return None;
+    }
+    let ip = init
+        .iter()
+        .enumerate()
+        .fold(*last, |ip, (i, &n)| ip + (n << (8 * (3 - i))));
+    Some(Ipv4Addr::from(ip as u32))
+}
+
+fn parse_ipv4_number(part: &str) -> Option<u64> {
+    let (digits, radix) = match part.strip_prefix("0x") {
+        Some(hex) => (hex, 16),
+        None if part.len() > 1 && part.starts_with('0') => (&part[1..], 8),
+        None => (part, 10),
+    };
+    if digits.is_empty() {
+        // `0x` alone is zero, an empty part is not a number
+        return (radix == 16).then_some(0);
+    }
+    if !digits.chars().all(|c| c.is_digit(radix)) || digits.len() > 20 {
+        return None;
+    }
+    u64::from_str_radix(digits, radix).ok()
+}
+
+fn is_private_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        // carrier-grade NAT, 100.64.0.0/10
+        || (a == 100 && (64..128).contains(&b))
+}
+
+fn is_private_v6(ip: Ipv6Addr) -> bool {
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return is_private_v4(v4);
+    }
+    let first = ip.segments()[0];
+    ip.is_loopback()
+        || ip.is_unspecified()
+        // unique local fc00::/7 and link-local fe80::/10
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shorthand_ipv4_is_normalized() {
+        let ip = |s: &str| parse_ipv4(s).map(|ip| ip.to_string());
+        assert_eq!(ip("127.0.0.1").as_deref(), Some("127.0.0.1"));
+        assert_eq!(ip("2130706433").as_deref(), Some("127.0.0.1"));
+        assert_eq!(ip("127.1").as_deref(), Some("127.0.0.1"));
+        assert_eq!(ip("10.1.2").as_deref(), Some("10.1.0.2"));
+        assert_eq!(ip("0x7f.0.0.1").as_deref(), Some("127.0.0.1"));
+        assert_eq!(ip("0x7f000001").as_deref(), Some("127.0.0.1"));
+        assert_eq!(ip("0177.0.0.01").as_deref(), Some("127.0.0.1"));
+        assert_eq!(ip("0x").as_deref(), Some("0.0.0.0"));
+        assert_eq!(ip("example.com"), None);
+        assert_eq!(ip("1.2.3.4.5"), None);
+        assert_eq!(ip("256.0.0.1"), None);
+        assert_eq!(ip("1.2.65536"), None);
+        assert_eq!(ip("4294967296"), None);
+        assert_eq!(ip("08.0.0.1"), None);
+        assert_eq!(ip("1..1"), None);
+    }
+
+    #[test]
+    fn private_ip_block_sees_shorthand_forms() {
+        let policy = UrlPolicy::new().block_private_ips(true);
+        for url in [
+            "http://127.0.0.1/",
+            "http://2130706433/",
+            "http://127.1/",
+            "http://0x7f.0.0.1/",
+            "http://0x7f000001:8080/",
+            "http://0177.0.0.1/",
+            "http://10.1/",
+            "http://localhost/",
+            "http://[::1]/",
+            "http://[::ffff:127.0.0.1]/",
+            "http://user@192.168.0.1/",
+        ] {
+            assert!(policy.check(url).is_err(), "{} was allowed", url);
+        }
+        for url in [
+            "https://example.com/",
+            "http://8.8.8.8/",
+            "http://134744072/",
+        ] {
+            assert!(policy.check(url).is_ok(), "{} was blocked", url);
+        }
+    }
+
+    #[test]
+    fn domain_rules() {
+        let policy = UrlPolicy::new()
+            .allow_domain("*.example.com")
+            .deny_domain("bad.example.com");
+        assert!(policy.check("https://api.example.com/x").is_ok());
+        assert!(policy.check("https://API.Example.com./x").is_ok());
+        assert!(policy.check("https://example.com/").is_err());
+        assert!(policy.check("https://bad.example.com/").is_err());
+        assert!(policy.check("ftp://api.example.com/").is_err());
+        assert!(matches!(
+            policy.check("not a url"),
+            Err(HttpErrorKind::InvalidUrl)
+        ));
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob_match("a*c*e", "abcde"));
+        assert!(glob_match("*", "anything"));
+        assert!(!glob_match("a*c", "abd"));
+        assert!(glob_match("exact", "exact"));
+    }
+}
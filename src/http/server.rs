@@ -0,0 +1,207 @@
+//! Running a function as an http handler.
+//!
+//! The host hands the incoming request to the function on stdin as JSON:
+//!
+//! ```text
+//! {"method":"GET","path":"/quote","query":"id=1","headers":{"accept":"*/*"},"body":""}
+//! ```
+//!
+//! and reads the response from stdout in the same shape:
+//! `{"status":200,"headers":{...},"body":"..."}`. Binary bodies travel base64
+//! encoded in `bodyBase64` instead of `body`. Input that is not such an
+//! envelope is treated as the body of a `POST /`; an envelope whose
+//! `bodyBase64` does not decode is answered with 400.
+//!
+//! ```ignore
+//! fn main() {
+//!     server::serve(|req| match req.path.as_str() {
+//!         "/health" => Response::text(200, "ok"),
+//!         _ => Response::new(404),
+//!     });
+//! }
+//! ```
+
+use std::{collections::BTreeMap, io::Write};
+
+use json::JsonValue;
+
+use crate::{base64, read_stdin_to_end};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    /// Header names are lowercased.
+    pub headers: BTreeMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.body).ok()
+    }
+
+    pub fn json(&self) -> Option<JsonValue> {
+        json::parse(self.text()?).ok()
+    }
+
+    fn parse(input: Vec<u8>) -> Result<Self, String> {
+        let envelope = std::str::from_utf8(&input)
+            .ok()
+            .and_then(|s| json::parse(s).ok())
+            .filter(|json| json["method"].is_string());
+        let Some(json) = envelope else {
+            return Ok(Request {
+                method: "POST".to_string(),
+                path: "/".to_string(),
+                body: input,
+                ..Default::default()
+            });
+        };
+        let body = match json["bodyBase64"].as_str() {
+            Some(encoded) => base64::decode(encoded).ok_or("bodyBase64 is not valid base64")?,
+            None => json["body"].as_str().unwrap_or("").as_bytes().to_vec(),
+        };
+        Ok(Request {
+            method: json["method"]
+                .as_str()
+                .unwrap_or("GET")
+                .to_ascii_uppercase(),
+            path: json["path"].as_str().unwrap_or("/").to_string(),
+            query: json["query"].as_str().map(str::to_string),
+            headers: json["headers"]
+                .entries()
+                .filter_map(|(k, v)| Some((k.to_ascii_lowercase(), v.as_str()?.to_string())))
+                .collect(),
+            body,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16) -> Self {
+        Response {
+            status,
+            headers: BTreeMap::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn text(status: u16, body: &str) -> Self {
+        Response::new(status)
+            .with_header("Content-Type", "text/plain; charset=utf-8")
+            .with_body(body.as_bytes().to_vec())
+    }
+
+    pub fn json(status: u16, body: &JsonValue) -> Self {
+        Response::new(status)
+            .with_header("Content-Type", "application/json")
+            .with_body(body.dump().into_bytes())
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    pub fn with_body(mut self, body: Vec<u8>) -> Self {
+        self.body = body;
+        self
+    }
+
+    fn dump(&self) -> String {
+        let mut json = JsonValue::new_object();
+        json["status"] = self.status.into();
+        json["headers"] = JsonValue::new_object();
+        for (name, value) in &self.headers {
+            json["headers"][name.as_str()] = value.as_str().into();
+        }
+        match std::str::from_utf8(&self.body) {
+            Ok(body) => json["body"] = body.into(),
+            Err(_) => json["bodyBase64"] = base64::encode(&self.body).into(),
+        }
+        json.dump()
+    }
+}
+
+/// Reads the request from stdin, runs `handler` and writes its response to stdout.
+/// A request that cannot be read or decoded is answered with 400.
+pub fn serve(handler: impl FnOnce(Request) -> Response) {
+    let request = read_stdin_to_end()
+        .map_err(|e| e.to_string())
+        .and_then(Request::parse);
+    let response = match request {
+        Ok(request) => handler(request),
+        Err(e) => Response::text(400, &e),
+    };
+    let mut stdout = std::io::stdout().lock();
+    let _ = stdout.write_all(response.dump().as_bytes());
+    let _ = stdout.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_text_envelope() {
+        let input = br#"{"method":"post","path":"/quote","query":"id=1","headers":{"Accept":"*/*"},"body":"hi"}"#;
+        let req = Request::parse(input.to_vec()).unwrap();
+        assert_eq!(req.method, "POST");
+        assert_eq!(req.path, "/quote");
+        assert_eq!(req.query.as_deref(), Some("id=1"));
+        assert_eq!(req.header("accept"), Some("*/*"));
+        assert_eq!(req.text(), Some("hi"));
+    }
+
+    #[test]
+    fn parses_base64_envelope() {
+        let input = br#"{"method":"PUT","path":"/bin","bodyBase64":"AP8Q"}"#;
+        let req = Request::parse(input.to_vec()).unwrap();
+        assert_eq!(req.body, [0x00, 0xff, 0x10]);
+        assert_eq!(req.query, None);
+    }
+
+    #[test]
+    fn malformed_base64_is_an_error() {
+        let input = br#"{"method":"PUT","path":"/bin","bodyBase64":"not base64!"}"#;
+        assert!(Request::parse(input.to_vec()).is_err());
+    }
+
+    #[test]
+    fn non_envelope_input_is_a_post_body() {
+        let req = Request::parse(b"plain text".to_vec()).unwrap();
+        assert_eq!(req.method, "POST");
+        assert_eq!(req.path, "/");
+        assert_eq!(req.body, b"plain text");
+        let req = Request::parse(br#"{"no":"method"}"#.to_vec()).unwrap();
+        assert_eq!(req.method, "POST");
+    }
+
+    #[test]
+    fn dumps_text_and_binary_bodies() {
+        let json = json::parse(&Response::text(200, "ok").dump()).unwrap();
+        assert_eq!(json["status"], 200);
+        assert_eq!(json["body"], "ok");
+        assert_eq!(json["headers"]["Content-Type"], "text/plain; charset=utf-8");
+        assert!(json["bodyBase64"].is_null());
+
+        let json = json::parse(&Response::new(200).with_body(vec![0xff, 0x00]).dump()).unwrap();
+        assert!(json["body"].is_null());
+        assert_eq!(json["bodyBase64"], "/wA=");
+    }
+}
@@ -0,0 +1,183 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::{HttpErrorKind, HttpOptions};
+
+/// Adds authentication to a request right before it is sent to the host.
+pub trait RequestSigner {
+    fn sign(&self, url: &str, opts: &mut HttpOptions) -> Result<(), HttpErrorKind>;
+}
+
+/// HMAC-SHA256 signer over a canonical form of the request:
+///
+/// ```text
+/// METHOD\nURL\nTIMESTAMP\nname:value\n...\nHEX(SHA256(BODY))
+/// ```
+///
+/// where the header lines are the signed headers, lowercased and sorted by name.
+/// A signed header the request lacks is signed with an empty value (`name:`).
+/// The hex signature, the unix timestamp and the signed header names are sent
+/// in the `signature_header`, `timestamp_header` and `signed_headers_header` headers.
+#[derive(Debug, Clone)]
+pub struct HmacSha256Signer {
+    key: Vec<u8>,
+    pub signed_headers: Vec<String>,
+    pub signature_header: String,
+    pub timestamp_header: String,
+    pub signed_headers_header: String,
+}
+
+impl HmacSha256Signer {
+    pub fn new(key: &[u8]) -> Self {
+        HmacSha256Signer {
+            key: key.to_vec(),
+            signed_headers: Vec::new(),
+            signature_header: "X-Signature".to_string(),
+            timestamp_header: "X-Timestamp".to_string(),
+            signed_headers_header: "X-Signed-Headers".to_string(),
+        }
+    }
+
+    pub fn canonical_request(&self, url: &str, opts: &HttpOptions, timestamp: u64) -> String {
+        let headers = opts.headers.clone().unwrap_or_default();
+        let signed = self
+            .signed_header_names()
+            .into_iter()
+            .map(|name| {
+                let value = headers
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(&name))
+                    .map_or("", |(_, v)| v.trim());
+                format!("{}:{}", name, value)
+            })
+            .collect::<Vec<_>>();
+        let mut lines = vec![
+            opts.method.to_ascii_uppercase(),
+            url.to_string(),
+            timestamp.to_string(),
+        ];
+        lines.extend(signed);
+        let body_hash = Sha256::digest(opts.body.as_deref().unwrap_or("").as_bytes());
+        lines.push(hex(&body_hash));
+        lines.join("\n")
+    }
+
+    pub fn sign_with_timestamp(
+        &self,
+        url: &str,
+        opts: &mut HttpOptions,
+        timestamp: u64,
+    ) -> Result<(), HttpErrorKind> {
+        let canonical = self.canonical_request(url, opts, timestamp);
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).map_err(|_| HttpErrorKind::RuntimeError)?;
+        mac.update(canonical.as_bytes());
+        let signature = hex(&mac.finalize().into_bytes());
+        opts.set_header(&self.timestamp_header, &timestamp.to_string());
+        opts.set_header(
+            &self.signed_headers_header,
+            &self.signed_header_names().join(";"),
+        );
+        opts.set_header(&self.signature_header, &signature);
+        Ok(())
+    }
+
+    /// `signed_headers` lowercased, sorted and without duplicates.
+    fn signed_header_names(&self) -> Vec<String> {
+        let mut names = self
+            .signed_headers
+            .iter()
+            .map(|h| h.to_ascii_lowercase())
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+impl RequestSigner for HmacSha256Signer {
+    fn sign(&self, url: &str, opts: &mut HttpOptions) -> Result<(), HttpErrorKind> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| HttpErrorKind::RuntimeError)?
+            .as_secs();
+        self.sign_with_timestamp(url, opts, timestamp)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://api.example.com/v1?a=1";
+
+    fn signer() -> HmacSha256Signer {
+        let mut signer = HmacSha256Signer::new(b"secret");
+        signer.signed_headers = vec![
+            "X-Missing".to_string(),
+            "X-Api-Key".to_string(),
+            "content-type".to_string(),
+        ];
+        signer
+    }
+
+    fn request() -> HttpOptions {
+        let mut opts = HttpOptions::new("post", 30, 30);
+        opts.set_header("Content-Type", "application/json");
+        opts.set_header("x-api-key", " abc ");
+        opts.set_header("X-Unsigned", "ignored");
+        opts.body = Some(r#"{"a":1}"#.to_string());
+        opts
+    }
+
+    #[test]
+    fn canonical_request_sorts_and_lowercases_headers() {
+        assert_eq!(
+            signer().canonical_request(URL, &request(), 1_700_000_000),
+            "POST\n\
+             https://api.example.com/v1?a=1\n\
+             1700000000\n\
+             content-type:application/json\n\
+             x-api-key:abc\n\
+             x-missing:\n\
+             015abd7f5cc57a2dd94b7590f04ad8084273905ee33ec5cebeae62276a97f862"
+        );
+    }
+
+    #[test]
+    fn signs_with_hmac_sha256() {
+        let mut opts = request();
+        signer()
+            .sign_with_timestamp(URL, &mut opts, 1_700_000_000)
+            .unwrap();
+        let headers = opts.headers.unwrap();
+        assert_eq!(
+            headers["X-Signature"],
+            "424413f5f672112db4a6f28e9f987ddf2d3f41312809677cc4f2e534b307ae1e"
+        );
+        assert_eq!(headers["X-Timestamp"], "1700000000");
+        assert_eq!(
+            headers["X-Signed-Headers"],
+            "content-type;x-api-key;x-missing"
+        );
+    }
+
+    #[test]
+    fn header_name_case_does_not_change_the_signature() {
+        let mut upper = HttpOptions::new("POST", 30, 30);
+        upper.set_header("X-API-KEY", "abc");
+        let mut lower = HttpOptions::new("POST", 30, 30);
+        lower.set_header("x-api-key", "abc");
+        let signer = signer();
+        assert_eq!(
+            signer.canonical_request(URL, &upper, 1),
+            signer.canonical_request(URL, &lower, 1)
+        );
+    }
+}
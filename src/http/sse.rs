@@ -0,0 +1,290 @@
+//! Server-sent events (`text/event-stream`) over chunked body reads.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::{
+    retry::{RetryPolicy, Retryable},
+    stream::ItemStream,
+    BlocklessHttp, HttpErrorKind, HttpOptions,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    pub id: Option<String>,
+    /// The `event:` type; `None` means the default `message` type.
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Consumes an event stream, reconnecting with `Last-Event-ID` when the
+/// connection drops. Reconnects back off per the retry policy unless the
+/// server asked for a specific delay with a `retry:` field.
+pub struct EventSource {
+    url: String,
+    headers: BTreeMap<String, String>,
+    policy: RetryPolicy,
+    http: Option<BlocklessHttp>,
+    buf: Vec<u8>,
+    parser: EventParser,
+    attempt: u32,
+    done: bool,
+}
+
+/// The `id:` and `retry:` state carried from one event block to the next.
+#[derive(Debug, Default)]
+struct EventParser {
+    last_event_id: Option<String>,
+    server_retry: Option<Duration>,
+}
+
+impl EventSource {
+    pub fn connect(url: &str, headers: &BTreeMap<String, String>) -> Result<Self, HttpErrorKind> {
+        let mut source = EventSource {
+            url: url.to_string(),
+            headers: headers.clone(),
+            policy: RetryPolicy::default(),
+            http: None,
+            buf: Vec::new(),
+            parser: EventParser::default(),
+            attempt: 0,
+            done: false,
+        };
+        source.http = source.open()?;
+        source.done = source.http.is_none();
+        Ok(source)
+    }
+
+    /// How often and how fast to reconnect. As with `RetryPolicy::run`,
+    /// `max_attempts` counts the first connection, so `RetryPolicy::none()`
+    /// never reconnects; the count resets whenever an event arrives.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.parser.last_event_id.as_deref()
+    }
+
+    /// `Ok(None)` when the server answered 204, which tells clients to stop.
+    fn open(&self) -> Result<Option<BlocklessHttp>, HttpErrorKind> {
+        let mut opts = HttpOptions::new("GET", 30, 300);
+        opts.headers = Some(self.headers.clone());
+        opts.set_header("Accept", "text/event-stream");
+        opts.set_header("Cache-Control", "no-cache");
+        if let Some(id) = &self.parser.last_event_id {
+            opts.set_header("Last-Event-ID", id);
+        }
+        let http = BlocklessHttp::open(&self.url, &opts)?;
+        match http.get_code() {
+            204 => Ok(None),
+            200..=299 => Ok(Some(http)),
+            _ => Err(HttpErrorKind::RequestError),
+        }
+    }
+
+    /// Reopens the stream, retrying failed connections that are retryable
+    /// until `max_attempts` is used up; the last error is returned then.
+    fn reconnect(&mut self) -> Result<bool, HttpErrorKind> {
+        self.http = None;
+        self.buf.clear();
+        let max_attempts = self.policy.max_attempts.max(1);
+        loop {
+            self.attempt += 1;
+            if self.attempt >= max_attempts {
+                return Ok(false);
+            }
+            let delay = self
+                .parser
+                .server_retry
+                .unwrap_or_else(|| self.policy.backoff(self.attempt));
+            std::thread::sleep(delay);
+            match self.open() {
+                Ok(http) => {
+                    self.http = http;
+                    return Ok(self.http.is_some());
+                }
+                Err(e) if e.is_retryable() && self.attempt + 1 < max_attempts => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl EventParser {
+    /// Applies one event block, returning the event if it carried any data.
+    fn dispatch(&mut self, block: &[u8]) -> Option<SseEvent> {
+        let mut event = SseEvent::default();
+        let mut data: Option<String> = None;
+        for line in block.split(|b| *b == b'\n' || *b == b'\r') {
+            if line.is_empty() || line[0] == b':' {
+                continue;
+            }
+            let line = String::from_utf8_lossy(line);
+            let (field, value) = line.split_once(':').unwrap_or((&line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "data" => {
+                    let data = data.get_or_insert_with(String::new);
+                    if !data.is_empty() {
+                        data.push('\n');
+                    }
+                    data.push_str(value);
+                }
+                "event" => event.event = Some(value.to_string()),
+                "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+                "retry" => {
+                    if let Ok(ms) = value.parse() {
+                        self.server_retry = Some(Duration::from_millis(ms));
+                    }
+                }
+                _ => {}
+            }
+        }
+        event.data = data?;
+        event.id = self.last_event_id.clone();
+        Some(event)
+    }
+}
+
+impl ItemStream<SseEvent> for EventSource {
+    type Error = HttpErrorKind;
+
+    fn next(&mut self) -> Option<Result<SseEvent, HttpErrorKind>> {
+        loop {
+            if let Some(block) = take_block(&mut self.buf) {
+                if let Some(event) = self.parser.dispatch(&block) {
+                    self.attempt = 0;
+                    return Some(Ok(event));
+                }
+                continue;
+            }
+            if self.done {
+                return None;
+            }
+            let mut chunk = [0u8; 1024];
+            let read = match &self.http {
+                Some(http) => http.read_body(&mut chunk),
+                None => Ok(0),
+            };
+            let reconnect = match read {
+                Ok(0) => self.reconnect(),
+                Ok(num) => {
+                    self.buf.extend_from_slice(&chunk[..num as usize]);
+                    continue;
+                }
+                Err(e) if e.is_retryable() => self.reconnect(),
+                Err(e) => Err(e),
+            };
+            match reconnect {
+                Ok(true) => {}
+                Ok(false) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Removes and returns the next event block (everything up to a blank line),
+/// accepting `\n`, `\r\n` and `\r` line endings.
+fn take_block(buf: &mut Vec<u8>) -> Option<Vec<u8>> {
+    let mut i = 0;
+    let mut line_start = 0;
+    while i < buf.len() {
+        let eol_len = match buf[i] {
+            b'\n' => 1,
+            b'\r' => match buf.get(i + 1) {
+                Some(b'\n') => 2,
+                Some(_) => 1,
+                // may be the first half of a \r\n split across reads
+                None => return None,
+            },
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        if i == line_start {
+            let block = buf[..i].to_vec();
+            buf.drain(..i + eol_len);
+            return Some(block);
+        }
+        i += eol_len;
+        line_start = i;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser() -> EventParser {
+        EventParser::default()
+    }
+
+    #[test]
+    fn blocks_end_at_a_blank_line_with_any_line_ending() {
+        for input in [
+            &b"data: a\n\nrest"[..],
+            b"data: a\r\n\r\nrest",
+            b"data: a\r\rrest",
+        ] {
+            let mut buf = input.to_vec();
+            let block = take_block(&mut buf).unwrap();
+            assert!(block.starts_with(b"data: a"), "{:?}", input);
+            assert_eq!(buf, b"rest");
+        }
+        let mut buf = b"data: a\n".to_vec();
+        assert_eq!(take_block(&mut buf), None);
+        assert_eq!(buf, b"data: a\n");
+    }
+
+    #[test]
+    fn crlf_split_across_reads() {
+        let mut buf = b"data: a\r\n\r".to_vec();
+        assert_eq!(take_block(&mut buf), None);
+        buf.extend_from_slice(b"\ndata: b\r\n\r\n");
+        let mut parser = parser();
+        let first = take_block(&mut buf).unwrap();
+        assert_eq!(parser.dispatch(&first).unwrap().data, "a");
+        // the \n completing the \r\n must not start an empty block
+        let second = take_block(&mut buf).unwrap();
+        assert_eq!(parser.dispatch(&second).unwrap().data, "b");
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn multi_line_data_and_event_type() {
+        let event = parser()
+            .dispatch(b"event: update\ndata: one\ndata:two\n: comment\ndata\nignored: x")
+            .unwrap();
+        assert_eq!(event.event.as_deref(), Some("update"));
+        assert_eq!(event.data, "one\ntwo\n");
+        assert_eq!(event.id, None);
+    }
+
+    #[test]
+    fn id_without_data_updates_last_event_id_only() {
+        let mut parser = parser();
+        assert_eq!(parser.dispatch(b"id: 7"), None);
+        assert_eq!(parser.last_event_id.as_deref(), Some("7"));
+        let event = parser.dispatch(b"data: x").unwrap();
+        assert_eq!(event.id.as_deref(), Some("7"));
+        // ids containing NUL are ignored
+        parser.dispatch(b"id: a\0b\ndata: y");
+        assert_eq!(parser.last_event_id.as_deref(), Some("7"));
+    }
+
+    #[test]
+    fn retry_field_sets_the_reconnect_delay() {
+        let mut parser = parser();
+        assert_eq!(parser.dispatch(b"retry: 2500"), None);
+        assert_eq!(parser.server_retry, Some(Duration::from_millis(2500)));
+        parser.dispatch(b"retry: soon");
+        assert_eq!(parser.server_retry, Some(Duration::from_millis(2500)));
+    }
+}
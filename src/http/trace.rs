@@ -0,0 +1,106 @@
+//! Per-request timing and correlation ids for `BlocklessHttp`.
+//!
+//! Every request gets a trace; it is reachable through `BlocklessHttp::trace()`
+//! and, once a hook is installed with [`set_hook`], handed to the hook when the
+//! request handle is dropped. Requests opened while a hook is installed also get
+//! a correlation id, sent as the [`CORRELATION_HEADER`] request header so host
+//! and server logs can be matched up with it.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        RwLock,
+    },
+    time::Duration,
+};
+
+pub const CORRELATION_HEADER: &str = "X-Correlation-Id";
+
+type Hook = Box<dyn Fn(&HttpTrace) + Send + Sync>;
+
+static HOOK: RwLock<Option<Hook>> = RwLock::new(None);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug)]
+pub struct HttpTrace {
+    /// Correlation id; `None` when no hook was installed as the request opened.
+    pub id: Option<String>,
+    pub method: String,
+    pub url: String,
+    /// Time spent encoding `HttpOptions` for the host.
+    pub serialize: Duration,
+    /// Time from handing the request to the host until the status line was back.
+    pub open: Duration,
+    body_nanos: AtomicU64,
+}
+
+impl HttpTrace {
+    pub(crate) fn new(method: &str, url: &str) -> Self {
+        HttpTrace {
+            id: hook_installed().then(new_id),
+            method: method.to_string(),
+            url: url.to_string(),
+            serialize: Duration::ZERO,
+            open: Duration::ZERO,
+            body_nanos: AtomicU64::new(0),
+        }
+    }
+
+    /// Total time spent in body reads so far.
+    pub fn body(&self) -> Duration {
+        Duration::from_nanos(self.body_nanos.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn add_body(&self, elapsed: Duration) {
+        self.body_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Installs `hook`, replacing any previous one.
+pub fn set_hook(hook: impl Fn(&HttpTrace) + Send + Sync + 'static) {
+    *HOOK.write().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(hook));
+}
+
+pub fn clear_hook() {
+    *HOOK.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// A counter hashed with a randomly keyed `RandomState`: unique within the
+/// instance and unlikely to repeat across instances, without a host call.
+fn new_id() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    format!("{:016x}", hasher.finish())
+}
+
+fn hook_installed() -> bool {
+    HOOK.read().unwrap_or_else(|e| e.into_inner()).is_some()
+}
+
+pub(crate) fn finish(trace: &HttpTrace) {
+    if let Some(hook) = HOOK.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        hook(trace);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // one test, since the hook is process wide
+    #[test]
+    fn ids_only_while_a_hook_is_installed() {
+        assert_eq!(HttpTrace::new("GET", "http://a/").id, None);
+        set_hook(|_| {});
+        let a = HttpTrace::new("GET", "http://a/").id.unwrap();
+        let b = HttpTrace::new("GET", "http://a/").id.unwrap();
+        assert_eq!(a.len(), 16);
+        assert_ne!(a, b);
+        clear_hook();
+        assert_eq!(HttpTrace::new("GET", "http://a/").id, None);
+    }
+}
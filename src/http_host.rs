@@ -26,3 +26,28 @@ extern "C" {
     #[link_name = "http_close"]
     pub(crate) fn http_close(handle: u32) -> u32;
 }
+
+// Streaming request bodies, enabled by the `http-upload` feature. Every
+// function returns 0 on success or a `codes::http` error code.
+#[cfg(feature = "http-upload")]
+#[link(wasm_import_module = "blockless_http")]
+extern "C" {
+    /// Like `http_req`, but returns the handle before the body is sent and
+    /// without waiting for the response.
+    #[link_name = "http_req_begin"]
+    pub(crate) fn http_req_begin(
+        url: *const u8,
+        url_len: u32,
+        opts: *const u8,
+        opts_len: u32,
+        fd: *mut u32,
+    ) -> u32;
+
+    /// Sends up to `buf_len` body bytes; writes how many were taken to `num`.
+    #[link_name = "http_write_body"]
+    pub(crate) fn http_write_body(handle: u32, buf: *const u8, buf_len: u32, num: *mut u32) -> u32;
+
+    /// Ends the body and waits for the response; writes its status code to `status`.
+    #[link_name = "http_req_finish"]
+    pub(crate) fn http_req_finish(handle: u32, status: *mut u32) -> u32;
+}
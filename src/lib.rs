@@ -1,17 +1,41 @@
+#[macro_use]
+mod ffi_debug;
+
+mod base64;
 mod cgi;
 mod cgi_host;
+mod context;
 mod error;
+#[cfg(feature = "events")]
+pub mod events;
+#[cfg(feature = "events")]
+mod events_host;
 mod http;
 mod http_host;
+pub mod limit;
 mod llm;
 mod memory;
 mod memory_host;
+#[cfg(feature = "oracles")]
+pub mod oracles;
+pub mod output;
+pub mod print;
+pub mod retry;
+#[cfg(feature = "runtime")]
+pub mod runtime;
+#[cfg(feature = "runtime")]
+mod runtime_host;
+mod session;
 mod socket;
 mod socket_host;
+pub mod stream;
+pub mod time;
 
 pub use cgi::*;
+pub use context::*;
 pub use error::*;
 pub use http::*;
 pub use llm::*;
 pub use memory::*;
+pub use session::*;
 pub use socket::*;
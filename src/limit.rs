@@ -0,0 +1,124 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::InvalidRate;
+
+/// Longest single wait `take` reports; `acquire` re-checks after each sleep.
+const MAX_WAIT: Duration = Duration::from_secs(3600);
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket rate limiter with one bucket per key (e.g. per host or per model).
+///
+/// Each bucket holds up to `burst` tokens and refills at `per_second` tokens a second.
+pub struct RateLimiter {
+    per_second: f64,
+    burst: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Fails if `per_second` is not a finite, positive number.
+    pub fn new(per_second: f64, burst: u32) -> Result<Self, InvalidRate> {
+        if !(per_second.is_finite() && per_second > 0.0) {
+            return Err(InvalidRate(per_second));
+        }
+        Ok(RateLimiter {
+            per_second,
+            burst: burst.max(1) as f64,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn per_second(&self) -> f64 {
+        self.per_second
+    }
+
+    /// Takes a token for `key` if one is available, without waiting.
+    pub fn try_acquire(&self, key: &str) -> bool {
+        self.take(key).is_zero()
+    }
+
+    /// Takes a token for `key`, sleeping until one is available.
+    pub fn acquire(&self, key: &str) {
+        loop {
+            let wait = self.take(key);
+            if wait.is_zero() {
+                return;
+            }
+            std::thread::sleep(wait);
+        }
+    }
+
+    /// Takes a token if available; otherwise returns how long until one will be.
+    fn take(&self, key: &str) -> Duration {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+        let refill = now.duration_since(bucket.updated).as_secs_f64() * self.per_second;
+        bucket.tokens = (bucket.tokens + refill).min(self.burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Duration::ZERO;
+        }
+        Duration::try_from_secs_f64((1.0 - bucket.tokens) / self.per_second)
+            .map_or(MAX_WAIT, |wait| wait.min(MAX_WAIT))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_rates_are_rejected() {
+        for rate in [0.0, -0.0, -1.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(RateLimiter::new(rate, 1).is_err(), "{} was accepted", rate);
+        }
+    }
+
+    #[test]
+    fn burst_then_wait() {
+        let limiter = RateLimiter::new(1.0, 2).unwrap();
+        assert!(limiter.try_acquire("a"));
+        assert!(limiter.try_acquire("a"));
+        assert!(!limiter.try_acquire("a"));
+        let wait = limiter.take("a");
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn keys_have_separate_buckets() {
+        let limiter = RateLimiter::new(1.0, 0).unwrap();
+        assert!(limiter.try_acquire("a"));
+        assert!(!limiter.try_acquire("a"));
+        assert!(limiter.try_acquire("b"));
+    }
+
+    #[test]
+    fn tiny_rates_cap_the_wait() {
+        let limiter = RateLimiter::new(f64::MIN_POSITIVE, 1).unwrap();
+        assert!(limiter.try_acquire("a"));
+        assert_eq!(limiter.take("a"), MAX_WAIT);
+    }
+
+    #[test]
+    fn acquire_refills() {
+        let limiter = RateLimiter::new(500.0, 1).unwrap();
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire("a");
+        }
+        assert!(start.elapsed() >= Duration::from_millis(3));
+    }
+}
@@ -1,7 +1,14 @@
+use crate::{stream::ByteStream, Context, ContextError, Handle};
 use json::JsonValue;
-use std::cmp::Ordering;
+use std::{cmp::Ordering, collections::BTreeMap};
 
-pub type Handle = u32;
+mod budget;
+pub mod sanitize;
+mod structured;
+mod template;
+
+pub use budget::*;
+pub use template::*;
 
 #[link(wasm_import_module = "blockless_llm")]
 extern "C" {
@@ -11,6 +18,18 @@ extern "C" {
     fn llm_get_model_options(buf: *mut u8, size: u32, num: *mut u32, fd: u32) -> i32;
     fn llm_prompt_request(prompt_ptr: *const u8, prompt_len: u32, fd: u32) -> i32;
     fn llm_read_prompt_response(buf: *mut u8, size: u32, num: *mut u32, fd: u32) -> i32;
+    /// Writes the models the host can serve as a JSON array of
+    /// `{"name": str, "quantizations": [str], "context_window": u32?,
+    /// "supports_tools": bool?}` and its length to `num`. Returns 0 on success
+    /// or a `codes::llm` error code. Only imported with the `llm-models` feature.
+    #[cfg(feature = "llm-models")]
+    fn llm_list_models(buf: *mut u8, size: u32, num: *mut u32) -> i32;
+    /// Writes the token counts of the handle's last completed prompt as JSON,
+    /// `{"prompt_tokens": u32, "completion_tokens": u32}`, and its length to
+    /// `num`. Returns 0 on success or a `codes::llm` error code. Only imported
+    /// with the `llm-usage` feature.
+    #[cfg(feature = "llm-usage")]
+    fn llm_read_usage(buf: *mut u8, size: u32, num: *mut u32, fd: u32) -> i32;
     fn llm_close(fd: u32) -> i32;
 }
 
@@ -21,25 +40,121 @@ pub struct BlocklessLlm {
     options: LlmOptions,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct LlmOptions {
     pub system_message: String,
-    // pub max_tokens: u32,
+    pub max_tokens: Option<u32>,
     pub temperature: Option<f32>,
     pub top_p: Option<f32>,
-    // pub frequency_penalty: f32,
-    // pub presence_penalty: f32,
+    pub frequency_penalty: Option<f32>,
+    pub presence_penalty: Option<f32>,
+    pub stop_sequences: Vec<String>,
+    pub seed: Option<u64>,
+    pub tools: Vec<ToolDefinition>,
+    /// JSON schema the model's reply must follow; see `BlocklessLlm::chat_request_json`.
+    pub response_schema: Option<JsonValue>,
 }
 
-impl Default for LlmOptions {
-    fn default() -> Self {
-        LlmOptions {
-            system_message: String::new(),
-            temperature: None,
-            top_p: None,
-            // frequency_penalty: 0.0,
-            // presence_penalty: 0.0,
+/// A function the model may ask the app to call instead of answering directly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub description: String,
+    /// JSON schema of the arguments object.
+    pub parameters: JsonValue,
+}
+
+impl ToolDefinition {
+    pub fn new(name: &str, description: &str, parameters: JsonValue) -> Self {
+        ToolDefinition {
+            name: name.to_string(),
+            description: description.to_string(),
+            parameters,
+        }
+    }
+
+    fn to_json(&self) -> JsonValue {
+        let mut json = JsonValue::new_object();
+        json["name"] = self.name.clone().into();
+        json["description"] = self.description.clone().into();
+        json["parameters"] = self.parameters.clone();
+        json
+    }
+
+    fn from_json(json: &JsonValue) -> Option<Self> {
+        Some(ToolDefinition {
+            name: json["name"].as_str()?.to_string(),
+            description: json["description"].as_str().unwrap_or("").to_string(),
+            parameters: json["parameters"].clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    pub id: Option<String>,
+    pub name: String,
+    pub arguments: JsonValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatResponse {
+    Text(String),
+    ToolCalls(Vec<ToolCall>),
+}
+
+/// Token counts for the last completed prompt, as reported by the host.
+#[cfg(feature = "llm-usage")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+#[cfg(feature = "llm-usage")]
+impl TokenUsage {
+    pub fn total_tokens(&self) -> u32 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+#[cfg(feature = "llm-usage")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatCompletion {
+    pub text: String,
+    /// `None` when the host does not report usage.
+    pub usage: Option<TokenUsage>,
+    /// Wall time from sending the prompt to reading the last response byte.
+    pub latency_ms: u64,
+}
+
+impl ChatResponse {
+    /// A response is a tool call when the host returns `{"tool_calls": [...]}`;
+    /// anything else is plain text.
+    fn parse(text: String) -> Self {
+        let Ok(json) = json::parse(&text) else {
+            return ChatResponse::Text(text);
+        };
+        if !json["tool_calls"].is_array() {
+            return ChatResponse::Text(text);
         }
+        let calls = json["tool_calls"]
+            .members()
+            .filter_map(|call| {
+                let name = call["name"].as_str()?.to_string();
+                // arguments may arrive as an object or as a JSON encoded string
+                let arguments = match call["arguments"].as_str() {
+                    Some(s) => json::parse(s).unwrap_or_else(|_| s.into()),
+                    None => call["arguments"].clone(),
+                };
+                Some(ToolCall {
+                    id: call["id"].as_str().map(str::to_string),
+                    name,
+                    arguments,
+                })
+            })
+            .collect();
+        ChatResponse::ToolCalls(calls)
     }
 }
 
@@ -48,29 +163,75 @@ impl LlmOptions {
         Self::default()
     }
 
+    pub fn with_response_schema(mut self, schema: JsonValue) -> Self {
+        self.response_schema = Some(schema);
+        self
+    }
+
     pub fn dump(&self) -> String {
         let mut json = JsonValue::new_object();
         json["system_message"] = self.system_message.clone().into();
         if let Some(temperature) = self.temperature {
-            json["temperature"] = temperature.into();
+            json["temperature"] = f32_json(temperature);
         }
         if let Some(top_p) = self.top_p {
-            json["top_p"] = top_p.into();
+            json["top_p"] = f32_json(top_p);
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            json["max_tokens"] = max_tokens.into();
+        }
+        if let Some(frequency_penalty) = self.frequency_penalty {
+            json["frequency_penalty"] = f32_json(frequency_penalty);
+        }
+        if let Some(presence_penalty) = self.presence_penalty {
+            json["presence_penalty"] = f32_json(presence_penalty);
+        }
+        if !self.stop_sequences.is_empty() {
+            json["stop_sequences"] = self.stop_sequences.clone().into();
+        }
+        if let Some(seed) = self.seed {
+            json["seed"] = seed.into();
+        }
+        if !self.tools.is_empty() {
+            json["tools"] = self
+                .tools
+                .iter()
+                .map(ToolDefinition::to_json)
+                .collect::<Vec<_>>()
+                .into();
+        }
+        if let Some(schema) = &self.response_schema {
+            json["response_schema"] = schema.clone();
         }
         json.dump()
     }
 }
 
+// `f32 -> f64` widening prints 0.7 as 0.699999988079071, which does not parse
+// back to the same f32; going through the shortest f32 representation does.
+fn f32_json(value: f32) -> JsonValue {
+    value
+        .to_string()
+        .parse::<f64>()
+        .unwrap_or(value as f64)
+        .into()
+}
+
 impl TryFrom<Vec<u8>> for LlmOptions {
     type Error = LlmErrorKind;
 
     fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
-        // Convert bytes to UTF-8 string
-        let json_str = String::from_utf8(bytes).map_err(|_| LlmErrorKind::Utf8Error)?;
+        LlmOptions::from_json(&parse_options_json(bytes)?)
+    }
+}
 
-        // Parse the JSON string
-        let json = json::parse(&json_str).map_err(|_| LlmErrorKind::OptionsNotSet)?;
+fn parse_options_json(bytes: Vec<u8>) -> Result<JsonValue, LlmErrorKind> {
+    let json_str = String::from_utf8(bytes).map_err(|_| LlmErrorKind::Utf8Error)?;
+    json::parse(&json_str).map_err(|_| LlmErrorKind::OptionsNotSet)
+}
 
+impl LlmOptions {
+    fn from_json(json: &JsonValue) -> Result<Self, LlmErrorKind> {
         // Extract system_message
         let system_message = json["system_message"]
             .as_str()
@@ -81,10 +242,99 @@ impl TryFrom<Vec<u8>> for LlmOptions {
             system_message,
             temperature: json["temperature"].as_f32(),
             top_p: json["top_p"].as_f32(),
+            max_tokens: json["max_tokens"].as_u32(),
+            frequency_penalty: json["frequency_penalty"].as_f32(),
+            presence_penalty: json["presence_penalty"].as_f32(),
+            stop_sequences: json["stop_sequences"]
+                .members()
+                .map(|s| s.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .ok_or(LlmErrorKind::OptionsNotSet)?,
+            seed: json["seed"].as_u64(),
+            tools: json["tools"]
+                .members()
+                .map(ToolDefinition::from_json)
+                .collect::<Option<Vec<_>>>()
+                .ok_or(LlmErrorKind::OptionsNotSet)?,
+            response_schema: match &json["response_schema"] {
+                JsonValue::Null => None,
+                schema => Some(schema.clone()),
+            },
+        })
+    }
+
+    /// Whether the options a host echoed back agree with these. Hosts only echo
+    /// the fields they support, so fields missing from `echo` are not compared.
+    fn matches_echo(&self, echo: &JsonValue) -> Result<bool, LlmErrorKind> {
+        let host = LlmOptions::from_json(echo)?;
+        let agree = |key: &str, same: bool| !echo.has_key(key) || same;
+        Ok(self.system_message == host.system_message
+            && agree("temperature", self.temperature == host.temperature)
+            && agree("top_p", self.top_p == host.top_p)
+            && agree("max_tokens", self.max_tokens == host.max_tokens)
+            && agree(
+                "frequency_penalty",
+                self.frequency_penalty == host.frequency_penalty,
+            )
+            && agree(
+                "presence_penalty",
+                self.presence_penalty == host.presence_penalty,
+            )
+            && agree("stop_sequences", self.stop_sequences == host.stop_sequences)
+            && agree("seed", self.seed == host.seed)
+            && agree("tools", self.tools == host.tools)
+            && agree(
+                "response_schema",
+                self.response_schema == host.response_schema,
+            ))
+    }
+}
+
+/// A model the host can serve.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelInfo {
+    pub name: String,
+    pub quantizations: Vec<String>,
+    /// Maximum prompt plus completion tokens, when the host knows it.
+    pub context_window: Option<u32>,
+    pub supports_tools: bool,
+}
+
+#[cfg(feature = "llm-models")]
+impl ModelInfo {
+    fn from_json(json: &JsonValue) -> Option<Self> {
+        Some(ModelInfo {
+            name: json["name"].as_str()?.to_string(),
+            quantizations: json["quantizations"]
+                .members()
+                .filter_map(|q| q.as_str().map(str::to_string))
+                .collect(),
+            context_window: json["context_window"].as_u32(),
+            supports_tools: json["supports_tools"].as_bool().unwrap_or(false),
         })
     }
 }
 
+/// Models the host can serve.
+#[cfg(feature = "llm-models")]
+pub fn available_models() -> Result<Vec<ModelInfo>, LlmErrorKind> {
+    let mut buf = vec![0u8; 16 * 1024];
+    let mut num: u32 = 0;
+    let rs = host_call!(
+        "blockless_llm",
+        "llm_list_models",
+        crate::ffi_debug::written(&buf, num),
+        unsafe { llm_list_models(buf.as_mut_ptr(), buf.len() as _, &mut num) }
+    );
+    if rs != 0 {
+        return Err(LlmErrorKind::from(rs));
+    }
+    let text = std::str::from_utf8(&buf[0..num as _]).map_err(|_| LlmErrorKind::Utf8Error)?;
+    let json = json::parse(text)
+        .map_err(|_| LlmErrorKind::InvalidJsonResponse("model list is not JSON".to_string()))?;
+    Ok(json.members().filter_map(ModelInfo::from_json).collect())
+}
+
 impl BlocklessLlm {
     pub fn new(model_name: &str) -> Result<Self, LlmErrorKind> {
         let mut llm = Self::default();
@@ -92,25 +342,79 @@ impl BlocklessLlm {
         Ok(llm)
     }
 
+    /// Like `new`, but fails with `ModelNotAvailable` unless `available_models`
+    /// lists `model_name`. Errors from listing the models are returned as is.
+    #[cfg(feature = "llm-models")]
+    pub fn new_checked(model_name: &str) -> Result<Self, LlmErrorKind> {
+        if !available_models()?.iter().any(|m| m.name == model_name) {
+            return Err(LlmErrorKind::ModelNotAvailable(model_name.to_string()));
+        }
+        Self::new(model_name)
+    }
+
+    /// This model's entry in `available_models`, if the host lists it.
+    #[cfg(feature = "llm-models")]
+    pub fn model_info(&self) -> Result<Option<ModelInfo>, LlmErrorKind> {
+        Ok(available_models()?
+            .into_iter()
+            .find(|m| m.name == self.model_name))
+    }
+
+    /// Adopts a handle opened elsewhere (e.g. passed in by the host). The model
+    /// name and options are read back from the host; the handle is closed on drop.
+    pub fn from_handle(handle: Handle) -> Result<Self, LlmErrorKind> {
+        let mut llm = Self::default();
+        llm.inner = handle;
+        llm.model_name = llm.get_model()?;
+        llm.options = llm.get_options().unwrap_or_default();
+        Ok(llm)
+    }
+
+    pub fn handle(&self) -> Handle {
+        self.inner
+    }
+
+    /// Closes the handle now instead of on drop, reporting the host's answer.
+    pub fn close(mut self) -> Result<(), LlmErrorKind> {
+        let handle = std::mem::take(&mut self.inner);
+        if handle == 0 {
+            return Ok(());
+        }
+        let rs = host_call!("blockless_llm", "llm_close", unsafe { llm_close(handle) });
+        if rs != 0 {
+            return Err(LlmErrorKind::from(rs));
+        }
+        Ok(())
+    }
+
     pub fn get_model(&self) -> Result<String, LlmErrorKind> {
         let mut buf = [0u8; 256];
         let mut num: u32 = 0;
-        let rs = unsafe {
-            llm_get_model_response(buf.as_mut_ptr(), buf.len() as _, &mut num, self.inner)
-        };
+        let rs = host_call!(
+            "blockless_llm",
+            "llm_get_model_response",
+            crate::ffi_debug::written(&buf, num),
+            unsafe {
+                llm_get_model_response(buf.as_mut_ptr(), buf.len() as _, &mut num, self.inner)
+            }
+        );
         if rs != 0 {
             return Err(LlmErrorKind::from(rs));
         }
-        let model = String::from_utf8(buf[0..num as _].to_vec()).unwrap();
-        Ok(model)
+        String::from_utf8(buf[0..num as _].to_vec()).map_err(|_| LlmErrorKind::Utf8Error)
     }
 
     pub fn set_model(&mut self, model_name: &str) -> Result<(), LlmErrorKind> {
         self.model_name = model_name.to_string();
         // handle (self.inner set from runtime)
-        let rs = unsafe {
-            llm_set_model_request(model_name.as_ptr(), model_name.len() as _, &mut self.inner)
-        };
+        let rs = host_call!(
+            "blockless_llm",
+            "llm_set_model_request",
+            model_name.as_bytes(),
+            unsafe {
+                llm_set_model_request(model_name.as_ptr(), model_name.len() as _, &mut self.inner)
+            }
+        );
         if rs != 0 {
             return Err(LlmErrorKind::from(rs));
         }
@@ -128,39 +432,54 @@ impl BlocklessLlm {
     }
 
     pub fn get_options(&self) -> Result<LlmOptions, LlmErrorKind> {
-        let mut buf = [0u8; 256];
+        LlmOptions::try_from(self.read_options()?)
+    }
+
+    fn read_options(&self) -> Result<Vec<u8>, LlmErrorKind> {
+        // tool schemas make the options far larger than the model name
+        let mut buf = vec![0u8; 16 * 1024];
         let mut num: u32 = 0;
-        let rs = unsafe {
-            llm_get_model_options(buf.as_mut_ptr(), buf.len() as _, &mut num, self.inner)
-        };
+        let rs = host_call!(
+            "blockless_llm",
+            "llm_get_model_options",
+            crate::ffi_debug::written(&buf, num),
+            unsafe {
+                llm_get_model_options(buf.as_mut_ptr(), buf.len() as _, &mut num, self.inner)
+            }
+        );
         if rs != 0 {
             println!("Error getting model options: {}", rs);
             return Err(LlmErrorKind::from(rs));
         }
 
-        // Convert buffer slice to Vec<u8> and try to parse into LlmOptions
-        LlmOptions::try_from(buf[0..num as usize].to_vec())
+        buf.truncate(num as usize);
+        Ok(buf)
     }
 
     pub fn set_options(&mut self, options: LlmOptions) -> Result<(), LlmErrorKind> {
         let options_json = options.dump();
         self.options = options;
-        let rs = unsafe {
-            llm_set_model_options_request(
-                options_json.as_ptr(),
-                options_json.len() as _,
-                self.inner,
-            )
-        };
+        let rs = host_call!(
+            "blockless_llm",
+            "llm_set_model_options_request",
+            options_json.as_bytes(),
+            unsafe {
+                llm_set_model_options_request(
+                    options_json.as_ptr(),
+                    options_json.len() as _,
+                    self.inner,
+                )
+            }
+        );
         if rs != 0 {
             return Err(LlmErrorKind::from(rs));
         }
 
         // Verify options were set correctly
-        let host_options = self.get_options()?;
-        if self.options != host_options {
+        let host_options = parse_options_json(self.read_options()?)?;
+        if !self.options.matches_echo(&host_options)? {
             println!(
-                "Options not set correctly in host/runtime; options: {:?}, options_from_host: {:?}",
+                "Options not set correctly in host/runtime; options: {:?}, options_from_host: {}",
                 self.options, host_options
             );
             return Err(LlmErrorKind::OptionsNotSet);
@@ -171,7 +490,12 @@ impl BlocklessLlm {
 
     pub fn chat_request(&self, prompt: &str) -> Result<String, LlmErrorKind> {
         // Perform the prompt request
-        let rs = unsafe { llm_prompt_request(prompt.as_ptr(), prompt.len() as _, self.inner) };
+        let rs = host_call!(
+            "blockless_llm",
+            "llm_prompt_request",
+            prompt.as_bytes(),
+            unsafe { llm_prompt_request(prompt.as_ptr(), prompt.len() as _, self.inner) }
+        );
         if rs != 0 {
             return Err(LlmErrorKind::from(rs));
         }
@@ -180,14 +504,187 @@ impl BlocklessLlm {
         self.get_chat_response()
     }
 
+    /// Like `chat_request`, but also reports token usage and latency.
+    #[cfg(feature = "llm-usage")]
+    pub fn chat_request_with_usage(&self, prompt: &str) -> Result<ChatCompletion, LlmErrorKind> {
+        let start = std::time::Instant::now();
+        let text = self.chat_request(prompt)?;
+        let latency_ms = start.elapsed().as_millis() as u64;
+        Ok(ChatCompletion {
+            text,
+            usage: self.read_usage(),
+            latency_ms,
+        })
+    }
+
+    #[cfg(feature = "llm-usage")]
+    fn read_usage(&self) -> Option<TokenUsage> {
+        let mut buf = [0u8; 256];
+        let mut num: u32 = 0;
+        let rs = host_call!(
+            "blockless_llm",
+            "llm_read_usage",
+            crate::ffi_debug::written(&buf, num),
+            unsafe { llm_read_usage(buf.as_mut_ptr(), buf.len() as _, &mut num, self.inner) }
+        );
+        if rs != 0 {
+            return None;
+        }
+        let json = json::parse(std::str::from_utf8(&buf[0..num as _]).ok()?).ok()?;
+        Some(TokenUsage {
+            prompt_tokens: json["prompt_tokens"].as_u32()?,
+            completion_tokens: json["completion_tokens"].as_u32()?,
+        })
+    }
+
+    /// Runs `instruction` over `content` that may not fit the model's window:
+    /// each chunk is answered separately, then the answers are merged with
+    /// `combine_instruction`, repeating until a single answer remains.
+    pub fn chat_request_chunked(
+        &self,
+        instruction: &str,
+        content: &str,
+        combine_instruction: &str,
+        budget: &ContextBudget,
+    ) -> Result<String, LlmErrorKind> {
+        let chunks = budget.split(instruction, content);
+        let mut answers = chunks
+            .iter()
+            .map(|chunk| self.chat_request(&format!("{}\n\n{}", instruction, chunk)))
+            .collect::<Result<Vec<_>, _>>()?;
+        loop {
+            match answers.len() {
+                0 => return Ok(String::new()),
+                1 => return Ok(answers.remove(0)),
+                _ => {}
+            }
+            let merged = answers.join("\n\n---\n\n");
+            let groups = budget.split(combine_instruction, &merged);
+            // one group means everything fits; otherwise keep reducing as long as it shrinks
+            let groups = if groups.len() >= answers.len() {
+                vec![budget.truncate(combine_instruction, &merged).to_string()]
+            } else {
+                groups
+            };
+            answers = groups
+                .iter()
+                .map(|group| self.chat_request(&format!("{}\n\n{}", combine_instruction, group)))
+                .collect::<Result<Vec<_>, _>>()?;
+        }
+    }
+
+    pub fn chat_with_template(
+        &self,
+        template: &PromptTemplate,
+        vars: &BTreeMap<String, String>,
+    ) -> Result<String, LlmErrorKind> {
+        self.chat_request(&template.render(vars)?)
+    }
+
+    /// Sends the prompt and parses the reply as JSON, validating it against
+    /// `LlmOptions::response_schema` when one is set. An unparsable or invalid
+    /// reply is retried once with a prompt asking the model to repair it.
+    pub fn chat_request_json(&self, prompt: &str) -> Result<JsonValue, LlmErrorKind> {
+        let reply = self.chat_request(prompt)?;
+        let problem = match self.parse_json_reply(&reply) {
+            Ok(json) => return Ok(json),
+            Err(problem) => problem,
+        };
+        let mut repair = format!(
+            "Your previous reply was not valid: {}.\nReply again with only the corrected JSON document.",
+            problem
+        );
+        if let Some(schema) = &self.options.response_schema {
+            repair.push_str(&format!(
+                "\nIt must match this JSON schema: {}",
+                schema.dump()
+            ));
+        }
+        repair.push_str(&format!("\nPrevious reply:\n{}", reply));
+        let reply = self.chat_request(&repair)?;
+        self.parse_json_reply(&reply)
+            .map_err(LlmErrorKind::InvalidJsonResponse)
+    }
+
+    /// `chat_request_json` converted into `T`.
+    pub fn chat_request_typed<T: TryFrom<JsonValue>>(
+        &self,
+        prompt: &str,
+    ) -> Result<T, LlmErrorKind> {
+        T::try_from(self.chat_request_json(prompt)?).map_err(|_| {
+            LlmErrorKind::InvalidJsonResponse("reply does not convert to the requested type".into())
+        })
+    }
+
+    fn parse_json_reply(&self, reply: &str) -> Result<JsonValue, String> {
+        let json = structured::extract_json(reply).ok_or("reply is not JSON")?;
+        if let Some(schema) = &self.options.response_schema {
+            structured::validate(schema, &json, "")?;
+        }
+        Ok(json)
+    }
+
+    /// Like `chat_request`, but reports tool calls requested by the model
+    /// (see `LlmOptions::tools`) instead of returning them as text.
+    pub fn chat(&self, prompt: &str) -> Result<ChatResponse, LlmErrorKind> {
+        self.chat_request(prompt).map(ChatResponse::parse)
+    }
+
+    /// Sends the result of a tool call back to the model and returns its next response.
+    pub fn submit_tool_result(
+        &self,
+        call: &ToolCall,
+        result: &str,
+    ) -> Result<ChatResponse, LlmErrorKind> {
+        let mut json = JsonValue::new_object();
+        json["role"] = "tool".into();
+        json["name"] = call.name.clone().into();
+        if let Some(id) = &call.id {
+            json["tool_call_id"] = id.clone().into();
+        }
+        json["content"] = result.into();
+        self.chat(&json.dump())
+    }
+
+    /// Like `chat_request`, failing early once the context is cancelled or past its deadline.
+    pub fn chat_request_with_context(
+        &self,
+        prompt: &str,
+        ctx: &Context,
+    ) -> Result<String, LlmErrorKind> {
+        ctx.check()?;
+        let rs = host_call!(
+            "blockless_llm",
+            "llm_prompt_request",
+            prompt.as_bytes(),
+            unsafe { llm_prompt_request(prompt.as_ptr(), prompt.len() as _, self.inner) }
+        );
+        if rs != 0 {
+            return Err(LlmErrorKind::from(rs));
+        }
+        self.read_chat_response(Some(ctx))
+    }
+
     fn get_chat_response(&self) -> Result<String, LlmErrorKind> {
+        self.read_chat_response(None)
+    }
+
+    fn read_chat_response(&self, ctx: Option<&Context>) -> Result<String, LlmErrorKind> {
         let mut vec = Vec::new();
         loop {
+            if let Some(ctx) = ctx {
+                ctx.check()?;
+            }
             let mut buf = [0u8; 4096]; // Larger buffer for LLM responses
             let mut num: u32 = 0;
-            let rs = unsafe {
-                llm_read_prompt_response(buf.as_mut_ptr(), buf.len() as _, &mut num, self.inner)
-            };
+            let rs = host_call!(
+                "blockless_llm",
+                "llm_read_prompt_response",
+                crate::ffi_debug::written(&buf, num),
+                unsafe {
+                    llm_read_prompt_response(buf.as_mut_ptr(), buf.len() as _, &mut num, self.inner)
+                }
+            );
 
             if rs != 0 {
                 return Err(LlmErrorKind::from(rs));
@@ -201,27 +698,77 @@ impl BlocklessLlm {
         String::from_utf8(vec).map_err(|_| LlmErrorKind::Utf8Error)
     }
 
-    // TODO: response streaming - not yet supported
-    // - read next available chunks
-    // - block until chunk is read, repeat until no more chunks
-    // pub fn read_response_chunk(&self, buf: &mut [u8]) -> Result<u32, LlmErrorKind> {
-    //     let mut num: u32 = 0;
-    //     let rs = unsafe {
-    //         llm_read_prompt_response(self.inner, buf.as_mut_ptr(), buf.len() as _, &mut num)
-    //     };
+    /// Sends the prompt and returns a stream over the response chunks as the host produces them.
+    pub fn chat_request_stream(&self, prompt: &str) -> Result<LlmResponseStream<'_>, LlmErrorKind> {
+        let rs = host_call!(
+            "blockless_llm",
+            "llm_prompt_request",
+            prompt.as_bytes(),
+            unsafe { llm_prompt_request(prompt.as_ptr(), prompt.len() as _, self.inner) }
+        );
+        if rs != 0 {
+            return Err(LlmErrorKind::from(rs));
+        }
+        Ok(LlmResponseStream {
+            llm: self,
+            done: false,
+        })
+    }
+
+    /// Reads the next available chunk of the pending response; 0 once it is complete.
+    pub fn read_response_chunk(&self, buf: &mut [u8]) -> Result<u32, LlmErrorKind> {
+        let mut num: u32 = 0;
+        let rs = host_call!(
+            "blockless_llm",
+            "llm_read_prompt_response",
+            crate::ffi_debug::written(buf, num),
+            unsafe {
+                llm_read_prompt_response(buf.as_mut_ptr(), buf.len() as _, &mut num, self.inner)
+            }
+        );
+        if rs != 0 {
+            return Err(LlmErrorKind::from(rs));
+        }
+        Ok(num)
+    }
+}
 
-    //     if rs != 0 {
-    //         return Err(LlmErrorKind::from(rs));
-    //     }
-    //     Ok(num)
-    // }
+pub struct LlmResponseStream<'a> {
+    llm: &'a BlocklessLlm,
+    done: bool,
+}
+
+impl ByteStream for LlmResponseStream<'_> {
+    type Error = LlmErrorKind;
+
+    fn next(&mut self) -> Option<Result<Vec<u8>, LlmErrorKind>> {
+        if self.done {
+            return None;
+        }
+        let mut buf = [0u8; 4096];
+        match self.llm.read_response_chunk(&mut buf) {
+            Ok(0) => {
+                self.done = true;
+                None
+            }
+            Ok(num) => Some(Ok(buf[0..num as _].to_vec())),
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
 }
 
 impl Drop for BlocklessLlm {
     fn drop(&mut self) {
-        unsafe {
-            llm_close(self.inner);
+        // handle 0 means set_model never ran (or close() already did)
+        if self.inner == 0 {
+            return;
         }
+        host_call!("blockless_llm", "llm_close", unsafe {
+            llm_close(self.inner)
+        });
     }
 }
 
@@ -230,16 +777,178 @@ pub enum LlmErrorKind {
     ModelNotSet,
     OptionsNotSet,
     Utf8Error,
+    Cancelled,
+    DeadlineExceeded,
+    MissingTemplateVariables(Vec<String>),
+    InvalidJsonResponse(String),
+    ModelNotAvailable(String),
     Unknown(i32),
 }
 
+impl std::fmt::Display for LlmErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            LlmErrorKind::ModelNotSet => write!(f, "Model not set."),
+            LlmErrorKind::OptionsNotSet => write!(f, "Options not set."),
+            LlmErrorKind::Utf8Error => write!(f, "Utf8 error."),
+            LlmErrorKind::Cancelled => write!(f, "Cancelled."),
+            LlmErrorKind::DeadlineExceeded => write!(f, "Deadline exceeded."),
+            LlmErrorKind::MissingTemplateVariables(ref vars) => {
+                write!(f, "Missing template variables: {}.", vars.join(", "))
+            }
+            LlmErrorKind::InvalidJsonResponse(ref problem) => {
+                write!(f, "Invalid JSON response: {}.", problem)
+            }
+            LlmErrorKind::ModelNotAvailable(ref model) => {
+                write!(f, "Model not available: {}.", model)
+            }
+            LlmErrorKind::Unknown(code) => write!(f, "Unknown error: {}.", code),
+        }
+    }
+}
+
+impl std::error::Error for LlmErrorKind {}
+
+impl From<ContextError> for LlmErrorKind {
+    fn from(e: ContextError) -> Self {
+        match e {
+            ContextError::Cancelled => LlmErrorKind::Cancelled,
+            ContextError::DeadlineExceeded => LlmErrorKind::DeadlineExceeded,
+        }
+    }
+}
+
 impl From<i32> for LlmErrorKind {
     fn from(code: i32) -> Self {
+        use crate::codes::llm::*;
         match code {
-            1 => LlmErrorKind::ModelNotSet,
-            2 => LlmErrorKind::OptionsNotSet,
-            3 => LlmErrorKind::Utf8Error,
+            MODEL_NOT_SET => LlmErrorKind::ModelNotSet,
+            OPTIONS_NOT_SET => LlmErrorKind::OptionsNotSet,
+            UTF8_ERROR => LlmErrorKind::Utf8Error,
             _ => LlmErrorKind::Unknown(code),
         }
     }
 }
+
+impl LlmErrorKind {
+    /// The host code this error maps from, `None` for errors raised by the SDK itself.
+    pub fn code(&self) -> Option<i32> {
+        use crate::codes::llm::*;
+        match self {
+            LlmErrorKind::ModelNotSet => Some(MODEL_NOT_SET),
+            LlmErrorKind::OptionsNotSet => Some(OPTIONS_NOT_SET),
+            LlmErrorKind::Utf8Error => Some(UTF8_ERROR),
+            LlmErrorKind::Unknown(code) => Some(*code),
+            LlmErrorKind::Cancelled
+            | LlmErrorKind::DeadlineExceeded
+            | LlmErrorKind::MissingTemplateVariables(_)
+            | LlmErrorKind::InvalidJsonResponse(_)
+            | LlmErrorKind::ModelNotAvailable(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "llm-models")]
+    #[test]
+    fn model_info_from_json() {
+        let json = json::parse(
+            r#"[{"name":"m","quantizations":["q4"],"context_window":4096,"supports_tools":true},{"quantizations":[]}]"#,
+        )
+        .unwrap();
+        let models = json
+            .members()
+            .filter_map(ModelInfo::from_json)
+            .collect::<Vec<_>>();
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].name, "m");
+        assert_eq!(models[0].quantizations, ["q4"]);
+        assert_eq!(models[0].context_window, Some(4096));
+        assert!(models[0].supports_tools);
+    }
+
+    fn echo(json: &str) -> JsonValue {
+        json::parse(json).unwrap()
+    }
+
+    fn options() -> LlmOptions {
+        LlmOptions {
+            system_message: "be brief".into(),
+            temperature: Some(0.7),
+            max_tokens: Some(64),
+            stop_sequences: vec!["END".into()],
+            seed: Some(7),
+            tools: vec![ToolDefinition::new("t", "d", JsonValue::new_object())],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn options_round_trip_through_json() {
+        let options = options().with_response_schema(echo(r#"{"type":"object"}"#));
+        let parsed = LlmOptions::try_from(options.dump().into_bytes()).unwrap();
+        assert_eq!(parsed, options);
+        assert!(options.matches_echo(&echo(&options.dump())).unwrap());
+    }
+
+    #[test]
+    fn fields_missing_from_the_echo_are_not_compared() {
+        // an older host only knows about the original options
+        let echoed = echo(r#"{"system_message":"be brief","temperature":0.7}"#);
+        assert!(options().matches_echo(&echoed).unwrap());
+    }
+
+    #[test]
+    fn echoed_fields_must_agree() {
+        let echoed = echo(r#"{"system_message":"be brief","temperature":0.2}"#);
+        assert!(!options().matches_echo(&echoed).unwrap());
+        let echoed = echo(r#"{"system_message":"other"}"#);
+        assert!(!options().matches_echo(&echoed).unwrap());
+        let echoed = echo(r#"{"system_message":"be brief","seed":8}"#);
+        assert!(!options().matches_echo(&echoed).unwrap());
+        assert!(options().matches_echo(&echo("{}")).is_err());
+    }
+
+    #[test]
+    fn tool_call_arguments_as_string_or_object() {
+        let reply = r#"{"tool_calls":[
+            {"id":"1","name":"weather","arguments":"{\"city\":\"Oslo\"}"},
+            {"name":"time","arguments":{"zone":"UTC"}},
+            {"name":"echo","arguments":"not json"},
+            {"arguments":{}}
+        ]}"#;
+        let ChatResponse::ToolCalls(calls) = ChatResponse::parse(reply.to_string()) else {
+            panic!("expected tool calls");
+        };
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[0].id.as_deref(), Some("1"));
+        assert_eq!(calls[0].name, "weather");
+        assert_eq!(calls[0].arguments["city"], "Oslo");
+        assert_eq!(calls[1].id, None);
+        assert_eq!(calls[1].arguments["zone"], "UTC");
+        assert_eq!(calls[2].arguments, "not json");
+    }
+
+    #[test]
+    fn other_replies_are_text() {
+        for reply in ["hello", r#"{"answer":42}"#, r#"{"tool_calls":"no"}"#] {
+            assert_eq!(
+                ChatResponse::parse(reply.to_string()),
+                ChatResponse::Text(reply.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn llm_codes_round_trip() {
+        use crate::codes::llm::*;
+        for code in [MODEL_NOT_SET, OPTIONS_NOT_SET, UTF8_ERROR, 0, -1, 100] {
+            assert_eq!(LlmErrorKind::from(code).code(), Some(code));
+        }
+        assert_eq!(LlmErrorKind::Cancelled.code(), None);
+        assert_eq!(LlmErrorKind::ModelNotAvailable("m".into()).code(), None);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{
+    sanitize::{approx_tokens, truncate_to_tokens},
+    ModelInfo,
+};
+
+/// How much text fits in a model's context window, using the rough
+/// `sanitize::approx_tokens` estimate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextBudget {
+    pub context_window: usize,
+    /// Tokens kept free for the model's answer.
+    pub reserved_for_completion: usize,
+}
+
+impl ContextBudget {
+    /// Reserves a quarter of the window, at most 1024 tokens, for the completion.
+    pub fn new(context_window: usize) -> Self {
+        ContextBudget {
+            context_window,
+            reserved_for_completion: (context_window / 4).min(1024),
+        }
+    }
+
+    /// `None` when the host did not report the model's window.
+    pub fn for_model(info: &ModelInfo) -> Option<Self> {
+        info.context_window.map(|window| Self::new(window as usize))
+    }
+
+    pub fn reserve_completion(mut self, tokens: usize) -> Self {
+        self.reserved_for_completion = tokens;
+        self
+    }
+
+    /// Tokens left for content once `overhead` (instructions, system text) is in the prompt.
+    pub fn available(&self, overhead: &str) -> usize {
+        self.context_window
+            .saturating_sub(self.reserved_for_completion)
+            .saturating_sub(approx_tokens(overhead))
+    }
+
+    pub fn fits(&self, overhead: &str, content: &str) -> bool {
+        approx_tokens(content) <= self.available(overhead)
+    }
+
+    pub fn truncate<'a>(&self, overhead: &str, content: &'a str) -> &'a str {
+        truncate_to_tokens(content, self.available(overhead))
+    }
+
+    /// Splits `content` into chunks that each fit next to `overhead`, breaking
+    /// at paragraph boundaries where possible.
+    pub fn split(&self, overhead: &str, content: &str) -> Vec<String> {
+        let limit = self.available(overhead).max(1);
+        let mut chunks = Vec::new();
+        let mut current = String::new();
+        for paragraph in content.split("\n\n").filter(|p| !p.trim().is_empty()) {
+            let mut paragraph = paragraph.trim();
+            while approx_tokens(paragraph) > limit {
+                let head = truncate_to_tokens(paragraph, limit);
+                push_chunk(&mut chunks, &mut current);
+                chunks.push(head.to_string());
+                paragraph = paragraph[head.len()..].trim_start();
+            }
+            let joined = approx_tokens(&current) + approx_tokens(paragraph) + 1;
+            if !current.is_empty() && joined > limit {
+                push_chunk(&mut chunks, &mut current);
+            }
+            if !current.is_empty() {
+                current.push_str("\n\n");
+            }
+            current.push_str(paragraph);
+        }
+        push_chunk(&mut chunks, &mut current);
+        chunks
+    }
+}
+
+fn push_chunk(chunks: &mut Vec<String>, current: &mut String) {
+    if !current.is_empty() {
+        chunks.push(std::mem::take(current));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_reserves_a_quarter_up_to_1024() {
+        assert_eq!(ContextBudget::new(2048).reserved_for_completion, 512);
+        assert_eq!(ContextBudget::new(32768).reserved_for_completion, 1024);
+    }
+
+    #[test]
+    fn available_subtracts_reserve_and_overhead() {
+        let budget = ContextBudget::new(100).reserve_completion(20);
+        assert_eq!(budget.available(""), 80);
+        assert_eq!(budget.available(&"a".repeat(40)), 70);
+        assert_eq!(budget.available(&"a".repeat(1000)), 0);
+    }
+
+    #[test]
+    fn fits_and_truncate() {
+        let budget = ContextBudget::new(10).reserve_completion(0);
+        assert!(budget.fits("", &"a".repeat(40)));
+        assert!(!budget.fits("", &"a".repeat(41)));
+        let text = "word ".repeat(20);
+        assert!(budget.fits("", budget.truncate("", &text)));
+    }
+
+    #[test]
+    fn split_keeps_every_chunk_within_budget() {
+        let budget = ContextBudget::new(20).reserve_completion(0);
+        let content = format!(
+            "{}\n\n{}\n\n{}",
+            "short one",
+            "another paragraph",
+            "long ".repeat(40)
+        );
+        let chunks = budget.split("", &content);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| budget.fits("", c)));
+        assert_eq!(chunks[0], "short one\n\nanother paragraph");
+        let rejoined: String = chunks.concat().split_whitespace().collect();
+        let original: String = content.split_whitespace().collect();
+        assert_eq!(rejoined, original);
+    }
+
+    #[test]
+    fn split_of_empty_content_is_empty() {
+        assert!(ContextBudget::new(100).split("", "\n\n  \n\n").is_empty());
+    }
+}
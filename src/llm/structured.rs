@@ -0,0 +1,146 @@
+use json::JsonValue;
+
+/// Parses a model reply as JSON, tolerating markdown code fences and prose
+/// around the JSON document.
+pub(crate) fn extract_json(text: &str) -> Option<JsonValue> {
+    let text = text.trim();
+    if let Ok(json) = json::parse(text) {
+        return Some(json);
+    }
+    let unfenced = text
+        .strip_prefix("```json")
+        .or_else(|| text.strip_prefix("```"))
+        .and_then(|t| t.trim_end().strip_suffix("```"));
+    if let Some(json) = unfenced.and_then(|t| json::parse(t.trim()).ok()) {
+        return Some(json);
+    }
+    let start = text.find(['{', '['])?;
+    let end = text.rfind(['}', ']'])?;
+    if end < start {
+        return None;
+    }
+    json::parse(&text[start..=end]).ok()
+}
+
+/// Checks `value` against the subset of JSON schema models are usually given:
+/// `type`, `properties`, `required`, `items` and `enum`.
+pub(crate) fn validate(schema: &JsonValue, value: &JsonValue, path: &str) -> Result<(), String> {
+    if let Some(ty) = schema["type"].as_str() {
+        let ok = match ty {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "number" => value.is_number(),
+            "integer" => value.as_f64().is_some_and(|n| n.fract() == 0.0),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            _ => true,
+        };
+        if !ok {
+            return Err(format!("{}: expected {}", display(path), ty));
+        }
+    }
+    if schema["enum"].is_array() && !schema["enum"].members().any(|v| v == value) {
+        return Err(format!("{}: value not in enum", display(path)));
+    }
+    for name in schema["required"].members().filter_map(|n| n.as_str()) {
+        if !value.has_key(name) {
+            return Err(format!(
+                "{}: missing required property {}",
+                display(path),
+                name
+            ));
+        }
+    }
+    for (name, prop) in schema["properties"].entries() {
+        if value.has_key(name) {
+            validate(prop, &value[name], &format!("{}.{}", path, name))?;
+        }
+    }
+    if !schema["items"].is_null() {
+        for (i, item) in value.members().enumerate() {
+            validate(&schema["items"], item, &format!("{}[{}]", path, i))?;
+        }
+    }
+    Ok(())
+}
+
+fn display(path: &str) -> &str {
+    if path.is_empty() {
+        "$"
+    } else {
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_bare_fenced_and_wrapped_json() {
+        let expected = json::object! { "a": 1 };
+        for reply in [
+            r#"{"a": 1}"#,
+            "```json\n{\"a\": 1}\n```",
+            "```\n{\"a\": 1}\n```  ",
+            "Sure! Here it is: {\"a\": 1} Let me know if you need more.",
+            "Here:\n```json\n{\"a\": 1}\n```\nDone.",
+        ] {
+            assert_eq!(extract_json(reply), Some(expected.clone()), "{}", reply);
+        }
+        assert_eq!(extract_json("List: [1, 2]."), Some(json::array![1, 2]));
+        assert_eq!(extract_json("no json here"), None);
+        assert_eq!(extract_json("} backwards {"), None);
+    }
+
+    fn schema() -> JsonValue {
+        json::parse(
+            r#"{
+                "type": "object",
+                "required": ["name", "tags"],
+                "properties": {
+                    "name": {"type": "string"},
+                    "mood": {"enum": ["happy", "sad"]},
+                    "tags": {"type": "array", "items": {"type": "integer"}},
+                    "owner": {"type": "object", "required": ["id"]}
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn check(value: &str) -> Result<(), String> {
+        validate(&schema(), &json::parse(value).unwrap(), "")
+    }
+
+    #[test]
+    fn accepts_matching_values() {
+        assert_eq!(check(r#"{"name":"x","tags":[1,2.0],"mood":"sad"}"#), Ok(()));
+    }
+
+    #[test]
+    fn reports_type_required_and_enum_errors_with_paths() {
+        assert_eq!(check("[]"), Err("$: expected object".to_string()));
+        assert_eq!(
+            check(r#"{"name":"x"}"#),
+            Err("$: missing required property tags".to_string())
+        );
+        assert_eq!(
+            check(r#"{"name":1,"tags":[]}"#),
+            Err(".name: expected string".to_string())
+        );
+        assert_eq!(
+            check(r#"{"name":"x","tags":[],"mood":"meh"}"#),
+            Err(".mood: value not in enum".to_string())
+        );
+        assert_eq!(
+            check(r#"{"name":"x","tags":[1,1.5]}"#),
+            Err(".tags[1]: expected integer".to_string())
+        );
+        assert_eq!(
+            check(r#"{"name":"x","tags":[],"owner":{}}"#),
+            Err(".owner: missing required property id".to_string())
+        );
+    }
+}
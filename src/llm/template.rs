@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+
+use crate::LlmErrorKind;
+
+/// A prompt with `{{name}}` placeholders.
+///
+/// Variables can be bound ahead of time with `partial`, and `render` fails with
+/// `LlmErrorKind::MissingTemplateVariables` instead of sending a prompt with holes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptTemplate {
+    template: String,
+    bound: BTreeMap<String, String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment<'a> {
+    Text(&'a str),
+    Var(&'a str),
+}
+
+impl PromptTemplate {
+    pub fn new(template: &str) -> Self {
+        PromptTemplate {
+            template: template.to_string(),
+            bound: BTreeMap::new(),
+        }
+    }
+
+    /// Placeholder names in order of first appearance, bound or not.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut vars: Vec<&str> = Vec::new();
+        for segment in self.segments() {
+            if let Segment::Var(name) = segment {
+                if !vars.contains(&name) {
+                    vars.push(name);
+                }
+            }
+        }
+        vars
+    }
+
+    /// Placeholders that still need a value.
+    pub fn missing(&self, vars: &BTreeMap<String, String>) -> Vec<String> {
+        self.variables()
+            .into_iter()
+            .filter(|name| !vars.contains_key(*name) && !self.bound.contains_key(*name))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Returns a template with `vars` bound; later values override earlier ones.
+    pub fn partial(&self, vars: &BTreeMap<String, String>) -> Self {
+        let mut template = self.clone();
+        template
+            .bound
+            .extend(vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+        template
+    }
+
+    pub fn render(&self, vars: &BTreeMap<String, String>) -> Result<String, LlmErrorKind> {
+        let missing = self.missing(vars);
+        if !missing.is_empty() {
+            return Err(LlmErrorKind::MissingTemplateVariables(missing));
+        }
+        let mut out = String::with_capacity(self.template.len());
+        for segment in self.segments() {
+            match segment {
+                Segment::Text(text) => out.push_str(text),
+                Segment::Var(name) => {
+                    let value = vars.get(name).or_else(|| self.bound.get(name));
+                    out.push_str(value.map(String::as_str).unwrap_or_default());
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    fn segments(&self) -> Vec<Segment<'_>> {
+        let mut segments = Vec::new();
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            segments.push(Segment::Text(&rest[..start]));
+            segments.push(Segment::Var(rest[start + 2..start + 2 + len].trim()));
+            rest = &rest[start + 2 + len + 2..];
+        }
+        segments.push(Segment::Text(rest));
+        segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn segments_split_text_and_trimmed_names() {
+        let template = PromptTemplate::new("Hi {{ name }}, {{name}} likes {{thing}}!");
+        assert_eq!(
+            template.segments(),
+            [
+                Segment::Text("Hi "),
+                Segment::Var("name"),
+                Segment::Text(", "),
+                Segment::Var("name"),
+                Segment::Text(" likes "),
+                Segment::Var("thing"),
+                Segment::Text("!"),
+            ]
+        );
+        assert_eq!(template.variables(), ["name", "thing"]);
+    }
+
+    #[test]
+    fn unclosed_placeholder_is_text() {
+        let template = PromptTemplate::new("a {{b}} c {{d");
+        assert_eq!(template.variables(), ["b"]);
+        assert_eq!(template.render(&vars(&[("b", "B")])).unwrap(), "a B c {{d");
+    }
+
+    #[test]
+    fn missing_and_render_errors() {
+        let template = PromptTemplate::new("{{a}} {{b}} {{c}}");
+        assert_eq!(template.missing(&vars(&[("b", "1")])), ["a", "c"]);
+        assert!(matches!(
+            template.render(&vars(&[("b", "1")])),
+            Err(LlmErrorKind::MissingTemplateVariables(missing)) if missing == ["a", "c"]
+        ));
+    }
+
+    #[test]
+    fn partial_binds_and_render_overrides() {
+        let template = PromptTemplate::new("{{greeting}}, {{name}}")
+            .partial(&vars(&[("greeting", "Hello"), ("name", "bound")]));
+        assert!(template.missing(&BTreeMap::new()).is_empty());
+        assert_eq!(template.render(&BTreeMap::new()).unwrap(), "Hello, bound");
+        assert_eq!(
+            template.render(&vars(&[("name", "Ada")])).unwrap(),
+            "Hello, Ada"
+        );
+        let rebound = template.partial(&vars(&[("greeting", "Hey")]));
+        assert_eq!(rebound.render(&BTreeMap::new()).unwrap(), "Hey, bound");
+    }
+}
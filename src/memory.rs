@@ -2,7 +2,12 @@ use crate::memory_host::*;
 
 pub fn read_stdin(buf: &mut [u8]) -> std::io::Result<u32> {
     let mut len = 0;
-    let errno = unsafe { memory_read(buf.as_mut_ptr(), buf.len() as _, &mut len) };
+    let errno = host_call!(
+        "blockless_memory",
+        "memory_read",
+        crate::ffi_debug::written(buf, len),
+        unsafe { memory_read(buf.as_mut_ptr(), buf.len() as _, &mut len) }
+    );
     if errno == 0 {
         return Ok(len);
     }
@@ -12,10 +17,62 @@ pub fn read_stdin(buf: &mut [u8]) -> std::io::Result<u32> {
 
 pub fn read_env_vars(buf: &mut [u8]) -> std::io::Result<u32> {
     let mut len = 0;
-    let errno = unsafe { env_var_read(buf.as_mut_ptr(), buf.len() as _, &mut len) };
+    let errno = host_call!(
+        "blockless_memory",
+        "env_var_read",
+        crate::ffi_debug::written(buf, len),
+        unsafe { env_var_read(buf.as_mut_ptr(), buf.len() as _, &mut len) }
+    );
     if errno == 0 {
         return Ok(len);
     }
     let err = std::io::Error::from_raw_os_error(errno as i32);
     Err(err)
 }
+
+/// Upper bound on what `read_stdin_to_end` (and the env vars read) buffers.
+pub const MAX_STDIN_BYTES: usize = 16 * 1024 * 1024;
+
+/// Reads all of stdin.
+///
+/// `memory_read` is not a cursor: every call copies stdin from the start, as
+/// much as fits in the buffer. So instead of reading until the host returns 0,
+/// this re-reads with a doubled buffer while the host fills it completely,
+/// failing with `InvalidData` once stdin would exceed `MAX_STDIN_BYTES`.
+pub fn read_stdin_to_end() -> std::io::Result<Vec<u8>> {
+    read_whole(read_stdin, "stdin")
+}
+
+/// All of the env vars payload; `env_var_read` copies from the start like `memory_read`.
+pub(crate) fn read_env_vars_to_end() -> std::io::Result<Vec<u8>> {
+    read_whole(read_env_vars, "env vars")
+}
+
+fn read_whole(read: fn(&mut [u8]) -> std::io::Result<u32>, what: &str) -> std::io::Result<Vec<u8>> {
+    let mut buf = vec![0u8; 4096];
+    loop {
+        let len = read(&mut buf)? as usize;
+        if len < buf.len() {
+            buf.truncate(len);
+            return Ok(buf);
+        }
+        if buf.len() > MAX_STDIN_BYTES {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} larger than {} bytes", what, MAX_STDIN_BYTES),
+            ));
+        }
+        // one byte of slack tells exactly MAX_STDIN_BYTES apart from more
+        buf.resize((buf.len() * 2).min(MAX_STDIN_BYTES + 1), 0);
+    }
+}
+
+pub fn read_stdin_string() -> std::io::Result<String> {
+    String::from_utf8(read_stdin_to_end()?)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+pub fn read_stdin_json() -> std::io::Result<json::JsonValue> {
+    json::parse(&read_stdin_string()?)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
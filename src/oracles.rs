@@ -0,0 +1,229 @@
+//! Price feeds over public market data APIs.
+//!
+//! ```ignore
+//! let feed = PriceFeed::new(CoinGecko);
+//! let btc = feed.get_price("bitcoin", "usd")?; // btc.value is fixed point, 6 decimals
+//! ```
+
+use std::collections::BTreeMap;
+
+use json::JsonValue;
+
+use crate::{BlocklessHttp, HttpErrorKind, HttpOptions};
+
+#[derive(Debug)]
+pub enum OracleErrorKind {
+    Http(HttpErrorKind),
+    /// The provider answered with a non-2xx status.
+    Status(u32),
+    InvalidResponse,
+    PriceNotFound {
+        asset: String,
+        vs_currency: String,
+    },
+}
+
+impl std::fmt::Display for OracleErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OracleErrorKind::Http(e) => write!(f, "Http error: {}.", e),
+            OracleErrorKind::Status(code) => write!(f, "Provider returned status {}.", code),
+            OracleErrorKind::InvalidResponse => write!(f, "Invalid provider response."),
+            OracleErrorKind::PriceNotFound { asset, vs_currency } => {
+                write!(f, "No {} price for {}.", vs_currency, asset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OracleErrorKind {}
+
+impl From<HttpErrorKind> for OracleErrorKind {
+    fn from(e: HttpErrorKind) -> Self {
+        OracleErrorKind::Http(e)
+    }
+}
+
+/// A market data source. Returns prices keyed by asset as the caller named it;
+/// assets the provider does not know are left out.
+pub trait PriceProvider {
+    fn fetch(
+        &self,
+        assets: &[&str],
+        vs_currency: &str,
+    ) -> Result<BTreeMap<String, f64>, OracleErrorKind>;
+}
+
+/// CoinGecko simple price API; assets are CoinGecko ids (`bitcoin`) and the
+/// currency a vs currency (`usd`).
+pub struct CoinGecko;
+
+impl PriceProvider for CoinGecko {
+    fn fetch(
+        &self,
+        assets: &[&str],
+        vs_currency: &str,
+    ) -> Result<BTreeMap<String, f64>, OracleErrorKind> {
+        let vs_currency = vs_currency.to_ascii_lowercase();
+        let url = format!(
+            "https://api.coingecko.com/api/v3/simple/price?ids={}&vs_currencies={}",
+            assets
+                .iter()
+                .map(|asset| percent_encode(asset))
+                .collect::<Vec<_>>()
+                .join(","),
+            percent_encode(&vs_currency)
+        );
+        let json = get_json(&url)?; // e.g. {"bitcoin":{"usd":67675}}
+        Ok(assets
+            .iter()
+            .filter_map(|asset| Some((asset.to_string(), json[*asset][&vs_currency].as_f64()?)))
+            .collect())
+    }
+}
+
+/// Binance spot ticker; assets are base symbols (`BTC`) and the currency the
+/// quote symbol (`USDT`).
+pub struct Binance;
+
+impl PriceProvider for Binance {
+    fn fetch(
+        &self,
+        assets: &[&str],
+        vs_currency: &str,
+    ) -> Result<BTreeMap<String, f64>, OracleErrorKind> {
+        let quote = vs_currency.to_ascii_uppercase();
+        let symbols = assets
+            .iter()
+            .map(|asset| {
+                let symbol = format!("{}{}", asset.to_ascii_uppercase(), quote);
+                format!("%22{}%22", percent_encode(&symbol))
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let url = format!(
+            "https://api.binance.com/api/v3/ticker/price?symbols=%5B{}%5D",
+            symbols
+        );
+        let json = get_json(&url)?; // e.g. [{"symbol":"BTCUSDT","price":"67675.01"}]
+        let prices = json
+            .members()
+            .filter_map(|ticker| {
+                let price = ticker["price"].as_str()?.parse::<f64>().ok()?;
+                Some((ticker["symbol"].as_str()?.to_string(), price))
+            })
+            .collect::<BTreeMap<_, _>>();
+        Ok(assets
+            .iter()
+            .filter_map(|asset| {
+                let symbol = format!("{}{}", asset.to_ascii_uppercase(), quote);
+                Some((asset.to_string(), *prices.get(&symbol)?))
+            })
+            .collect())
+    }
+}
+
+/// Percent-encodes everything but RFC 3986 unreserved characters, so caller
+/// supplied ids cannot add or split query parameters.
+fn percent_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for b in text.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+fn get_json(url: &str) -> Result<JsonValue, OracleErrorKind> {
+    let http = BlocklessHttp::open(url, &HttpOptions::new("GET", 30, 10))?;
+    if !(200..300).contains(&http.get_code()) {
+        return Err(OracleErrorKind::Status(http.get_code()));
+    }
+    let body = http.get_all_body()?;
+    let body = String::from_utf8(body).map_err(|_| OracleErrorKind::InvalidResponse)?;
+    json::parse(&body).map_err(|_| OracleErrorKind::InvalidResponse)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Price {
+    pub asset: String,
+    pub vs_currency: String,
+    /// Price scaled by `10^decimals`, so it can be carried on chain without floats.
+    pub value: u64,
+    pub decimals: u32,
+}
+
+impl Price {
+    pub fn as_f64(&self) -> f64 {
+        self.value as f64 / 10f64.powi(self.decimals as i32)
+    }
+}
+
+pub struct PriceFeed<P> {
+    provider: P,
+    decimals: u32,
+}
+
+impl<P: PriceProvider> PriceFeed<P> {
+    /// A feed reporting prices with 6 decimals.
+    pub fn new(provider: P) -> Self {
+        PriceFeed {
+            provider,
+            decimals: 6,
+        }
+    }
+
+    pub fn with_decimals(mut self, decimals: u32) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    pub fn get_price(&self, asset: &str, vs_currency: &str) -> Result<Price, OracleErrorKind> {
+        let mut prices = self.get_prices(&[asset], vs_currency)?;
+        Ok(prices.remove(0))
+    }
+
+    /// Fetches all `assets` in one provider request; fails if any is missing.
+    pub fn get_prices(
+        &self,
+        assets: &[&str],
+        vs_currency: &str,
+    ) -> Result<Vec<Price>, OracleErrorKind> {
+        let prices = self.provider.fetch(assets, vs_currency)?;
+        let scale = 10f64.powi(self.decimals as i32);
+        assets
+            .iter()
+            .map(|asset| {
+                let price = prices
+                    .get(*asset)
+                    .ok_or_else(|| OracleErrorKind::PriceNotFound {
+                        asset: asset.to_string(),
+                        vs_currency: vs_currency.to_string(),
+                    })?;
+                Ok(Price {
+                    asset: asset.to_string(),
+                    vs_currency: vs_currency.to_string(),
+                    value: (price * scale).round() as u64,
+                    decimals: self.decimals,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_encodes_query_values() {
+        assert_eq!(percent_encode("bitcoin"), "bitcoin");
+        assert_eq!(percent_encode("usd-coin_v2.x~"), "usd-coin_v2.x~");
+        assert_eq!(percent_encode("bitcoin&x=y"), "bitcoin%26x%3Dy");
+        assert_eq!(percent_encode("a b,\"é"), "a%20b%2C%22%C3%A9");
+    }
+}
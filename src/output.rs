@@ -0,0 +1,30 @@
+use std::io::Write;
+
+use json::JsonValue;
+
+/// Writes `{"status":"ok","data":...}` to stdout and exits with code 0.
+pub fn ok<T: Into<JsonValue>>(data: T) -> ! {
+    let mut json = JsonValue::new_object();
+    json["status"] = "ok".into();
+    json["data"] = data.into();
+    emit(&json, 0)
+}
+
+/// Writes `{"status":"error","error":{"code":...,"message":...}}` to stdout and
+/// exits with `code`. A code of 0 is reported as 1 so failures are never mistaken for success.
+pub fn err(code: i32, msg: &str) -> ! {
+    let code = if code == 0 { 1 } else { code };
+    let mut json = JsonValue::new_object();
+    json["status"] = "error".into();
+    json["error"]["code"] = code.into();
+    json["error"]["message"] = msg.into();
+    emit(&json, code)
+}
+
+fn emit(json: &JsonValue, code: i32) -> ! {
+    let mut stdout = std::io::stdout().lock();
+    // process::exit skips destructors, so flush explicitly or the envelope may be lost.
+    let _ = writeln!(stdout, "{}", json.dump());
+    let _ = stdout.flush();
+    std::process::exit(code)
+}
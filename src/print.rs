@@ -0,0 +1,81 @@
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Trace => "TRACE",
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[doc(hidden)]
+pub fn write_line(stderr: bool, level: Option<Level>, args: std::fmt::Arguments<'_>) {
+    fn write(out: &mut dyn Write, level: Option<Level>, args: std::fmt::Arguments<'_>) {
+        let _ = match level {
+            Some(level) => writeln!(out, "[{}] {}", level, args),
+            None => writeln!(out, "{}", args),
+        };
+        // wasi stdout is buffered and a trap loses whatever was not flushed yet.
+        let _ = out.flush();
+    }
+    if stderr {
+        write(&mut std::io::stderr().lock(), level, args);
+    } else {
+        write(&mut std::io::stdout().lock(), level, args);
+    }
+}
+
+/// `println!` that flushes stdout after every line when running on the host,
+/// so output is not lost if the module traps. Accepts an optional level tag:
+/// `bls_println!(level: Level::Warn, "retrying {}", url)`.
+#[macro_export]
+macro_rules! bls_println {
+    () => {
+        $crate::bls_println!("")
+    };
+    (level: $level:expr, $($arg:tt)*) => {
+        $crate::print::write_line(false, Some($level), format_args!($($arg)*))
+    };
+    ($($arg:tt)*) => {{
+        #[cfg(target_arch = "wasm32")]
+        $crate::print::write_line(false, None, format_args!($($arg)*));
+        #[cfg(not(target_arch = "wasm32"))]
+        println!($($arg)*);
+    }};
+}
+
+/// `eprintln!` counterpart of [`bls_println!`].
+#[macro_export]
+macro_rules! bls_eprintln {
+    () => {
+        $crate::bls_eprintln!("")
+    };
+    (level: $level:expr, $($arg:tt)*) => {
+        $crate::print::write_line(true, Some($level), format_args!($($arg)*))
+    };
+    ($($arg:tt)*) => {{
+        #[cfg(target_arch = "wasm32")]
+        $crate::print::write_line(true, None, format_args!($($arg)*));
+        #[cfg(not(target_arch = "wasm32"))]
+        eprintln!($($arg)*);
+    }};
+}
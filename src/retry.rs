@@ -0,0 +1,221 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use crate::{HttpErrorKind, SocketErrorKind};
+
+/// Errors that know whether repeating the failed host call may succeed.
+pub trait Retryable {
+    fn is_retryable(&self) -> bool;
+}
+
+impl Retryable for HttpErrorKind {
+    fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            HttpErrorKind::RequestError
+                | HttpErrorKind::RuntimeError
+                | HttpErrorKind::TooManySessions
+                | HttpErrorKind::ConnectTimeout
+                | HttpErrorKind::ReadTimeout
+        )
+    }
+}
+
+impl Retryable for SocketErrorKind {
+    fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            SocketErrorKind::ConnectRefused | SocketErrorKind::ConnectionReset
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32) -> Self {
+        RetryPolicy {
+            max_attempts,
+            ..Default::default()
+        }
+    }
+
+    pub fn none() -> Self {
+        Self::new(1)
+    }
+
+    /// Backoff to wait after the given (1-based) failed attempt.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self
+            .multiplier
+            .max(1.0)
+            .powi(attempt.saturating_sub(1) as i32);
+        let secs = (self.initial_backoff.as_secs_f64() * exp).min(self.max_backoff.as_secs_f64());
+        let backoff = Duration::from_secs_f64(secs);
+        if !self.jitter {
+            return backoff;
+        }
+        // equal jitter: pick uniformly in [backoff / 2, backoff]
+        let half = backoff / 2;
+        let millis = half.as_millis() as u64;
+        if millis == 0 {
+            return backoff;
+        }
+        half + Duration::from_millis(random_u64() % (millis + 1))
+    }
+
+    pub fn run<T, E, F>(&self, op: F) -> Result<T, E>
+    where
+        E: Retryable,
+        F: FnMut(u32) -> Result<T, E>,
+    {
+        self.run_with(op, E::is_retryable)
+    }
+
+    /// Like `run`, with a caller supplied predicate deciding which errors are retried.
+    pub fn run_with<T, E, F, P>(&self, mut op: F, is_retryable: P) -> Result<T, E>
+    where
+        F: FnMut(u32) -> Result<T, E>,
+        P: Fn(&E) -> bool,
+    {
+        let max_attempts = self.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            match op(attempt) {
+                Ok(v) => return Ok(v),
+                Err(e) if attempt < max_attempts && is_retryable(&e) => {
+                    std::thread::sleep(self.backoff(attempt));
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+fn random_u64() -> u64 {
+    // RandomState is seeded from the host's entropy source, which is all we need
+    // for jitter. Deliberately not `runtime::random_bytes`: that import is
+    // opt-in and a module importing it fails to instantiate on older runtimes.
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fast(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+            multiplier: 2.0,
+            jitter: false,
+        }
+    }
+
+    #[test]
+    fn backoff_grows_and_caps() {
+        let policy = RetryPolicy {
+            jitter: false,
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(20), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn jitter_stays_within_half_to_full_backoff() {
+        let policy = RetryPolicy::default();
+        for attempt in 1..8 {
+            let full = RetryPolicy {
+                jitter: false,
+                ..policy.clone()
+            }
+            .backoff(attempt);
+            for _ in 0..50 {
+                let b = policy.backoff(attempt);
+                assert!(b >= full / 2 && b <= full, "{:?} outside {:?}", b, full);
+            }
+        }
+    }
+
+    #[test]
+    fn run_stops_after_max_attempts() {
+        let mut calls = 0;
+        let rs: Result<(), _> = fast(3).run(|attempt| {
+            calls += 1;
+            assert_eq!(attempt, calls);
+            Err(HttpErrorKind::RequestError)
+        });
+        assert!(matches!(rs, Err(HttpErrorKind::RequestError)));
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn run_returns_first_success() {
+        let rs = fast(5).run(|attempt| {
+            if attempt < 3 {
+                Err(SocketErrorKind::ConnectRefused)
+            } else {
+                Ok(attempt)
+            }
+        });
+        assert_eq!(rs.unwrap(), 3);
+    }
+
+    #[test]
+    fn zero_max_attempts_still_runs_once() {
+        let mut calls = 0;
+        let _: Result<(), _> = fast(0).run(|_| {
+            calls += 1;
+            Err(HttpErrorKind::RequestError)
+        });
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn run_with_stops_on_non_retryable() {
+        let mut calls = 0;
+        let rs: Result<(), u32> = fast(5).run_with(
+            |_| {
+                calls += 1;
+                Err(calls)
+            },
+            |e| *e < 2,
+        );
+        assert_eq!(rs, Err(2));
+        assert_eq!(calls, 2);
+
+        let mut calls = 0;
+        let _: Result<(), _> = fast(5).run(|_| {
+            calls += 1;
+            Err(HttpErrorKind::InvalidUrl)
+        });
+        assert_eq!(calls, 1);
+    }
+}
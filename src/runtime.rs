@@ -0,0 +1,69 @@
+use crate::{runtime_host, RuntimeErrorKind};
+
+/// Fuel left in this invocation's execution budget.
+///
+/// Long running loops (crawls, LLM chains) can poll this and stop with partial
+/// results before the runtime kills the instance.
+pub fn fuel_remaining() -> Result<u64, RuntimeErrorKind> {
+    let mut fuel = 0u64;
+    let rs = host_call!("blockless_runtime", "fuel_remaining", unsafe {
+        runtime_host::fuel_remaining(&mut fuel)
+    });
+    if rs != 0 {
+        return Err(RuntimeErrorKind::from(rs));
+    }
+    Ok(fuel)
+}
+
+/// Bytes of linear memory the instance may still grow by.
+pub fn memory_remaining() -> Result<u64, RuntimeErrorKind> {
+    let mut bytes = 0u64;
+    let rs = host_call!("blockless_runtime", "memory_remaining", unsafe {
+        runtime_host::memory_remaining(&mut bytes)
+    });
+    if rs != 0 {
+        return Err(RuntimeErrorKind::from(rs));
+    }
+    Ok(bytes)
+}
+
+/// Wall clock time from the host, in milliseconds since the Unix epoch.
+///
+/// Prefer this over `SystemTime::now()`, which deterministic runtimes may stub.
+pub fn now_unix_ms() -> Result<u64, RuntimeErrorKind> {
+    let mut ms = 0u64;
+    let rs = host_call!("blockless_runtime", "now_unix_ms", unsafe {
+        runtime_host::now_unix_ms(&mut ms)
+    });
+    if rs != 0 {
+        return Err(RuntimeErrorKind::from(rs));
+    }
+    Ok(ms)
+}
+
+/// Host monotonic clock in nanoseconds; only differences between readings are meaningful.
+pub fn monotonic_ns() -> Result<u64, RuntimeErrorKind> {
+    let mut ns = 0u64;
+    let rs = host_call!("blockless_runtime", "monotonic_ns", unsafe {
+        runtime_host::monotonic_ns(&mut ns)
+    });
+    if rs != 0 {
+        return Err(RuntimeErrorKind::from(rs));
+    }
+    Ok(ns)
+}
+
+/// `n` bytes from the host's entropy source.
+pub fn random_bytes(n: usize) -> Result<Vec<u8>, RuntimeErrorKind> {
+    let mut buf = vec![0u8; n];
+    let rs = host_call!(
+        "blockless_runtime",
+        "random_bytes",
+        buf.as_slice(),
+        unsafe { runtime_host::random_bytes(buf.as_mut_ptr(), buf.len() as _) }
+    );
+    if rs != 0 {
+        return Err(RuntimeErrorKind::from(rs));
+    }
+    Ok(buf)
+}
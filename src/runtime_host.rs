@@ -0,0 +1,27 @@
+//! `blockless_runtime` host module, enabled by the `runtime` feature.
+//!
+//! Every function returns 0 on success or a `codes::runtime` error code, and
+//! writes its result through the pointer it is given only on success.
+
+#[link(wasm_import_module = "blockless_runtime")]
+extern "C" {
+    /// Writes the fuel left in this invocation's budget to `fuel`.
+    #[link_name = "fuel_remaining"]
+    pub(crate) fn fuel_remaining(fuel: *mut u64) -> u32;
+
+    /// Writes the bytes of linear memory the instance may still grow by to `bytes`.
+    #[link_name = "memory_remaining"]
+    pub(crate) fn memory_remaining(bytes: *mut u64) -> u32;
+
+    /// Writes milliseconds since the Unix epoch to `ms`.
+    #[link_name = "now_unix_ms"]
+    pub(crate) fn now_unix_ms(ms: *mut u64) -> u32;
+
+    /// Writes a monotonic clock reading in nanoseconds to `ns`.
+    #[link_name = "monotonic_ns"]
+    pub(crate) fn monotonic_ns(ns: *mut u64) -> u32;
+
+    /// Fills all `len` bytes at `buf` from the host's entropy source.
+    #[link_name = "random_bytes"]
+    pub(crate) fn random_bytes(buf: *mut u8, len: u32) -> u32;
+}
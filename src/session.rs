@@ -0,0 +1,253 @@
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    limit::RateLimiter, split_url, BlocklessHttp, BlocklessLlm, HttpErrorKind, HttpOptions,
+    LlmErrorKind, UrlPolicy,
+};
+
+struct Cookie {
+    value: String,
+    path: String,
+    expires: Option<Instant>,
+}
+
+/// Shared state for an app talking to several host modules: default headers and
+/// a cookie jar and per-host rate limiting for http, and one LLM handle per model
+/// that lives as long as the session.
+///
+/// Cookies are host-only: `Domain` is ignored, and so is `Expires`, so a cookie
+/// without `Max-Age` lasts as long as the session. `Max-Age` and `Path` are honoured.
+#[derive(Default)]
+pub struct Session {
+    default_headers: BTreeMap<String, String>,
+    // host -> cookie name -> cookie
+    cookies: BTreeMap<String, BTreeMap<String, Cookie>>,
+    llms: BTreeMap<String, BlocklessLlm>,
+    http_limiter: Option<Arc<RateLimiter>>,
+    url_policy: Option<UrlPolicy>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_default_header(&mut self, name: &str, value: &str) {
+        self.default_headers
+            .insert(name.to_string(), value.to_string());
+    }
+
+    pub fn default_headers(&self) -> &BTreeMap<String, String> {
+        &self.default_headers
+    }
+
+    /// Unexpired cookies stored for `host`, whatever their path.
+    pub fn cookies(&self, host: &str) -> BTreeMap<String, String> {
+        let now = Instant::now();
+        self.cookies
+            .get(&host.to_ascii_lowercase())
+            .into_iter()
+            .flatten()
+            .filter(|(_, c)| c.expires.is_none_or(|at| at > now))
+            .map(|(name, c)| (name.clone(), c.value.clone()))
+            .collect()
+    }
+
+    pub fn clear_cookies(&mut self) {
+        self.cookies.clear();
+    }
+
+    /// Throttles http requests per host; the limiter may be shared with other code.
+    pub fn set_http_rate_limiter(&mut self, limiter: Arc<RateLimiter>) {
+        self.http_limiter = Some(limiter);
+    }
+
+    /// Rejects http requests the policy does not allow before they reach the host.
+    pub fn set_url_policy(&mut self, policy: UrlPolicy) {
+        self.url_policy = Some(policy);
+    }
+
+    pub fn http(&mut self) -> SessionHttp<'_> {
+        SessionHttp { session: self }
+    }
+
+    /// Returns the session's handle for `model`, creating it on first use.
+    pub fn llm(&mut self, model: &str) -> Result<&mut BlocklessLlm, LlmErrorKind> {
+        if !self.llms.contains_key(model) {
+            let llm = BlocklessLlm::new(model)?;
+            self.llms.insert(model.to_string(), llm);
+        }
+        self.llms.get_mut(model).ok_or(LlmErrorKind::ModelNotSet)
+    }
+}
+
+pub struct SessionHttp<'a> {
+    session: &'a mut Session,
+}
+
+impl SessionHttp<'_> {
+    /// Opens a request with the session's default headers and cookies applied;
+    /// headers already set in `opts` win. `Set-Cookie` from the response is stored.
+    pub fn open(&mut self, url: &str, opts: &HttpOptions) -> Result<BlocklessHttp, HttpErrorKind> {
+        if let Some(policy) = &self.session.url_policy {
+            policy.check(url)?;
+        }
+        let host = split_url(url).map(|(_, host)| host).unwrap_or_default();
+        let path = url_path(url);
+        if let Some(limiter) = &self.session.http_limiter {
+            limiter.acquire(&host);
+        }
+        let mut opts = opts.clone();
+        let mut headers = self.session.default_headers.clone();
+        let cookie = matching_cookies(&self.session.cookies, &host, path)
+            .map(|(k, c)| format!("{}={}", k, c.value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if !cookie.is_empty() {
+            headers.insert("Cookie".to_string(), cookie);
+        }
+        headers.extend(opts.headers.take().unwrap_or_default());
+        opts.headers = Some(headers);
+
+        let http = BlocklessHttp::open(url, &opts)?;
+        if let Ok(set_cookie) = http.get_header("set-cookie") {
+            store_cookie(&mut self.session.cookies, &host, path, &set_cookie);
+        }
+        Ok(http)
+    }
+}
+
+/// Unexpired cookies for `host` whose path covers `path`.
+fn matching_cookies<'a>(
+    jar: &'a BTreeMap<String, BTreeMap<String, Cookie>>,
+    host: &str,
+    path: &'a str,
+) -> impl Iterator<Item = (&'a String, &'a Cookie)> {
+    let now = Instant::now();
+    jar.get(host)
+        .into_iter()
+        .flatten()
+        .filter(move |(_, c)| c.expires.is_none_or(|at| at > now) && path_matches(&c.path, path))
+}
+
+fn store_cookie(
+    jar: &mut BTreeMap<String, BTreeMap<String, Cookie>>,
+    host: &str,
+    request_path: &str,
+    set_cookie: &str,
+) {
+    let mut attrs = set_cookie.split(';');
+    let Some((name, value)) = attrs.next().and_then(|pair| pair.split_once('=')) else {
+        return;
+    };
+    let mut cookie = Cookie {
+        value: value.trim().to_string(),
+        path: default_path(request_path).to_string(),
+        expires: None,
+    };
+    for attr in attrs {
+        let (key, value) = attr.split_once('=').unwrap_or((attr, ""));
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "path" if value.starts_with('/') => cookie.path = value.to_string(),
+            "max-age" => {
+                let Ok(secs) = value.parse::<i64>() else {
+                    continue;
+                };
+                // a zero or negative Max-Age deletes the cookie
+                if secs <= 0 {
+                    if let Some(cookies) = jar.get_mut(host) {
+                        cookies.remove(name.trim());
+                    }
+                    return;
+                }
+                cookie.expires = Instant::now().checked_add(Duration::from_secs(secs as u64));
+            }
+            _ => {}
+        }
+    }
+    jar.entry(host.to_string())
+        .or_default()
+        .insert(name.trim().to_string(), cookie);
+}
+
+/// The path of `url`, `/` when it has none.
+fn url_path(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let rest = rest.split(['?', '#']).next().unwrap_or("");
+    match rest.find('/') {
+        Some(i) => &rest[i..],
+        None => "/",
+    }
+}
+
+/// RFC 6265 default cookie path: the request path up to its last `/`.
+fn default_path(request_path: &str) -> &str {
+    match request_path.rfind('/') {
+        Some(0) | None => "/",
+        Some(i) => &request_path[..i],
+    }
+}
+
+fn path_matches(cookie_path: &str, request_path: &str) -> bool {
+    match request_path.strip_prefix(cookie_path) {
+        Some(rest) => rest.is_empty() || cookie_path.ends_with('/') || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sent(
+        jar: &BTreeMap<String, BTreeMap<String, Cookie>>,
+        host: &str,
+        path: &str,
+    ) -> Vec<String> {
+        matching_cookies(jar, host, path)
+            .map(|(k, _)| k.clone())
+            .collect()
+    }
+
+    #[test]
+    fn ipv6_hosts_keep_their_address() {
+        let (_, host) = split_url("http://[::1]:8080/a").unwrap();
+        assert_eq!(host, "::1");
+        assert_eq!(url_path("http://[::1]:8080/a/b?q=1"), "/a/b");
+        assert_eq!(url_path("https://example.com"), "/");
+        assert_eq!(url_path("https://example.com?q=/x"), "/");
+    }
+
+    #[test]
+    fn path_attribute_scopes_cookies() {
+        let mut jar = BTreeMap::new();
+        store_cookie(&mut jar, "h", "/", "a=1; Path=/api");
+        store_cookie(&mut jar, "h", "/docs/page", "b=2");
+        assert_eq!(sent(&jar, "h", "/api/v1"), ["a"]);
+        assert!(sent(&jar, "h", "/apix").is_empty());
+        assert_eq!(sent(&jar, "h", "/docs/other"), ["b"]);
+        assert!(sent(&jar, "other", "/api").is_empty());
+    }
+
+    #[test]
+    fn max_age_expires_and_deletes() {
+        let mut jar = BTreeMap::new();
+        store_cookie(&mut jar, "h", "/", "a=1; Max-Age=3600");
+        store_cookie(&mut jar, "h", "/", "b=2; max-age=99999999999999");
+        assert_eq!(sent(&jar, "h", "/"), ["a", "b"]);
+        store_cookie(&mut jar, "h", "/", "a=; Max-Age=0");
+        assert_eq!(sent(&jar, "h", "/"), ["b"]);
+    }
+
+    #[test]
+    fn malformed_set_cookie_is_ignored() {
+        let mut jar = BTreeMap::new();
+        store_cookie(&mut jar, "h", "/", "no-equals-sign; Path=/");
+        assert!(jar.is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+use super::create_tcp_bind_socket;
+use crate::{socket_host::*, SocketErrorKind};
+
+pub struct TcpListener {
+    fd: u32,
+}
+
+impl TcpListener {
+    pub fn bind(addr: &str) -> Result<Self, SocketErrorKind> {
+        create_tcp_bind_socket(addr).map(|fd| TcpListener { fd })
+    }
+
+    pub fn fd(&self) -> u32 {
+        self.fd
+    }
+
+    /// Blocks until a client connects.
+    pub fn accept(&self) -> Result<TcpStream, SocketErrorKind> {
+        let mut fd: u32 = 0;
+        let rs = host_call!("blockless_socket", "tcp_accept", unsafe {
+            tcp_accept(self.fd, &mut fd)
+        });
+        if rs != 0 {
+            return Err(SocketErrorKind::from(rs));
+        }
+        Ok(TcpStream { fd })
+    }
+
+    pub fn incoming(&self) -> impl Iterator<Item = Result<TcpStream, SocketErrorKind>> + '_ {
+        std::iter::repeat_with(move || self.accept())
+    }
+}
+
+impl Drop for TcpListener {
+    fn drop(&mut self) {
+        host_call!("blockless_socket", "tcp_close", unsafe {
+            tcp_close(self.fd)
+        });
+    }
+}
+
+pub struct TcpStream {
+    fd: u32,
+}
+
+impl TcpStream {
+    pub fn connect(addr: &str) -> Result<Self, SocketErrorKind> {
+        let mut fd: u32 = 0;
+        let rs = host_call!("blockless_socket", "tcp_connect", addr.as_bytes(), unsafe {
+            tcp_connect(addr.as_ptr(), addr.len() as _, &mut fd)
+        });
+        if rs != 0 {
+            return Err(SocketErrorKind::from(rs));
+        }
+        Ok(TcpStream { fd })
+    }
+
+    pub fn fd(&self) -> u32 {
+        self.fd
+    }
+
+    /// Reads into `buf`, returning 0 once the peer closed the connection.
+    pub fn read_bytes(&self, buf: &mut [u8]) -> Result<u32, SocketErrorKind> {
+        let mut num: u32 = 0;
+        let rs = host_call!(
+            "blockless_socket",
+            "tcp_read",
+            crate::ffi_debug::written(buf, num),
+            unsafe { tcp_read(self.fd, buf.as_mut_ptr(), buf.len() as _, &mut num) }
+        );
+        if rs != 0 {
+            return Err(SocketErrorKind::from(rs));
+        }
+        Ok(num)
+    }
+
+    pub fn write_bytes(&self, buf: &[u8]) -> Result<u32, SocketErrorKind> {
+        let mut num: u32 = 0;
+        let rs = host_call!("blockless_socket", "tcp_write", buf, unsafe {
+            tcp_write(self.fd, buf.as_ptr(), buf.len() as _, &mut num)
+        });
+        if rs != 0 {
+            return Err(SocketErrorKind::from(rs));
+        }
+        Ok(num)
+    }
+
+    pub fn shutdown(&self, how: std::net::Shutdown) -> Result<(), SocketErrorKind> {
+        let how = match how {
+            std::net::Shutdown::Read => 0,
+            std::net::Shutdown::Write => 1,
+            std::net::Shutdown::Both => 2,
+        };
+        let rs = host_call!("blockless_socket", "tcp_shutdown", unsafe {
+            tcp_shutdown(self.fd, how)
+        });
+        if rs != 0 {
+            return Err(SocketErrorKind::from(rs));
+        }
+        Ok(())
+    }
+}
+
+impl std::io::Read for TcpStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        Ok(self.read_bytes(buf)? as usize)
+    }
+}
+
+impl std::io::Write for TcpStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(self.write_bytes(buf)? as usize)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for TcpStream {
+    fn drop(&mut self) {
+        host_call!("blockless_socket", "tcp_close", unsafe {
+            tcp_close(self.fd)
+        });
+    }
+}
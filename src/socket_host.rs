@@ -7,3 +7,35 @@ extern "C" {
         fd: *mut u32,
     ) -> u32;
 }
+
+// Stream socket imports, enabled by the `tcp` feature. Every function returns 0
+// on success or a `codes::socket` error code.
+#[cfg(feature = "tcp")]
+#[link(wasm_import_module = "blockless_socket")]
+extern "C" {
+    /// Blocks until a client connects to the listening socket `fd`; writes the
+    /// connection's fd to `conn_fd`.
+    #[link_name = "tcp_accept"]
+    pub(crate) fn tcp_accept(fd: u32, conn_fd: *mut u32) -> u32;
+
+    /// Connects to `addr` (`host:port`); writes the connection's fd to `fd`.
+    #[link_name = "tcp_connect"]
+    pub(crate) fn tcp_connect(addr: *const u8, addr_len: u32, fd: *mut u32) -> u32;
+
+    /// Blocks until data is available; writes the byte count to `num`, 0 once
+    /// the peer closed the connection.
+    #[link_name = "tcp_read"]
+    pub(crate) fn tcp_read(fd: u32, buf: *mut u8, buf_len: u32, num: *mut u32) -> u32;
+
+    /// Writes up to `buf_len` bytes; writes how many were taken to `num`.
+    #[link_name = "tcp_write"]
+    pub(crate) fn tcp_write(fd: u32, buf: *const u8, buf_len: u32, num: *mut u32) -> u32;
+
+    /// `how` is 0 for read, 1 for write and 2 for both halves.
+    #[link_name = "tcp_shutdown"]
+    pub(crate) fn tcp_shutdown(fd: u32, how: u32) -> u32;
+
+    /// Closes a listener or connection fd.
+    #[link_name = "tcp_close"]
+    pub(crate) fn tcp_close(fd: u32) -> u32;
+}
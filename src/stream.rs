@@ -0,0 +1,173 @@
+use std::io::Read;
+
+/// A source of byte chunks read from the host on demand.
+pub trait ByteStream {
+    type Error;
+
+    /// Blocks until the next chunk is available; `None` once the stream is exhausted.
+    fn next(&mut self) -> Option<Result<Vec<u8>, Self::Error>>;
+
+    fn reader(self) -> StreamReader<Self>
+    where
+        Self: Sized,
+    {
+        StreamReader {
+            stream: self,
+            chunk: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    fn chunks(self) -> ByteStreamIter<Self>
+    where
+        Self: Sized,
+    {
+        ByteStreamIter(self)
+    }
+
+    /// Drains the stream into a single buffer.
+    fn read_to_end(&mut self) -> Result<Vec<u8>, Self::Error> {
+        let mut data = Vec::new();
+        while let Some(chunk) = self.next() {
+            data.extend_from_slice(&chunk?);
+        }
+        Ok(data)
+    }
+}
+
+/// A source of decoded items (events, pages, ...) read from the host on demand.
+pub trait ItemStream<T> {
+    type Error;
+
+    /// Blocks until the next item is available; `None` once the stream is exhausted.
+    fn next(&mut self) -> Option<Result<T, Self::Error>>;
+
+    fn items(self) -> ItemStreamIter<Self, T>
+    where
+        Self: Sized,
+    {
+        ItemStreamIter(self, std::marker::PhantomData)
+    }
+}
+
+pub struct ByteStreamIter<S>(S);
+
+impl<S: ByteStream> Iterator for ByteStreamIter<S> {
+    type Item = Result<Vec<u8>, S::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+pub struct ItemStreamIter<S, T>(S, std::marker::PhantomData<T>);
+
+impl<S: ItemStream<T>, T> Iterator for ItemStreamIter<S, T> {
+    type Item = Result<T, S::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// `std::io::Read` over a `ByteStream`, buffering the part of a chunk not yet read.
+pub struct StreamReader<S> {
+    stream: S,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl<S> StreamReader<S> {
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S> Read for StreamReader<S>
+where
+    S: ByteStream,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.pos >= self.chunk.len() {
+            match self.stream.next() {
+                Some(Ok(chunk)) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                Some(Err(e)) => return Err(std::io::Error::other(e)),
+                None => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Chunks(std::vec::IntoIter<Result<Vec<u8>, std::io::Error>>);
+
+    impl ByteStream for Chunks {
+        type Error = std::io::Error;
+
+        fn next(&mut self) -> Option<Result<Vec<u8>, Self::Error>> {
+            self.0.next()
+        }
+    }
+
+    fn chunks(chunks: &[&[u8]]) -> Chunks {
+        Chunks(
+            chunks
+                .iter()
+                .map(|c| Ok(c.to_vec()))
+                .collect::<Vec<_>>()
+                .into_iter(),
+        )
+    }
+
+    #[test]
+    fn reader_splits_chunks_larger_than_the_buffer() {
+        let mut reader = chunks(&[b"abcdefgh", b"ij"]).reader();
+        let mut buf = [0u8; 3];
+        let mut out = Vec::new();
+        loop {
+            let n = reader.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            assert!(n <= 3);
+            out.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(out, b"abcdefghij");
+    }
+
+    #[test]
+    fn reader_joins_chunks_smaller_than_the_buffer() {
+        let mut out = String::new();
+        chunks(&[b"a", b"", b"bc", b"d"])
+            .reader()
+            .read_to_string(&mut out)
+            .unwrap();
+        assert_eq!(out, "abcd");
+    }
+
+    #[test]
+    fn reader_surfaces_stream_errors() {
+        let stream =
+            Chunks(vec![Ok(b"ok".to_vec()), Err(std::io::Error::other("broken"))].into_iter());
+        let mut reader = stream.reader();
+        let mut buf = [0u8; 8];
+        assert_eq!(reader.read(&mut buf).unwrap(), 2);
+        assert!(reader.read(&mut buf).is_err());
+    }
+
+    #[test]
+    fn read_to_end_concatenates() {
+        assert_eq!(chunks(&[b"ab", b"cd"]).read_to_end().unwrap(), b"abcd");
+    }
+}
@@ -0,0 +1,157 @@
+use std::time::{Duration, Instant};
+
+use crate::TimeoutExceeded;
+
+/// A point in time by which work has to be finished.
+///
+/// Budgets compose: a stage deadline never extends past its parent, e.g.
+///
+/// ```ignore
+/// let run = Deadline::after(Duration::from_secs(10));
+/// let page = run.stage("scrape", Duration::from_secs(6), |d| scrape(d))?;
+/// let answer = run.stage("llm", Duration::from_secs(3), |d| summarize(d, page))?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline {
+    /// `None` never expires.
+    at: Option<Instant>,
+}
+
+impl Deadline {
+    pub fn at(at: Instant) -> Self {
+        Deadline { at: Some(at) }
+    }
+
+    pub fn never() -> Self {
+        Deadline { at: None }
+    }
+
+    /// A deadline `duration` from now; one too far out to represent, such as
+    /// `Duration::MAX`, never expires.
+    pub fn after(duration: Duration) -> Self {
+        Deadline {
+            at: Instant::now().checked_add(duration),
+        }
+    }
+
+    /// `None` for a deadline that never expires.
+    pub fn instant(&self) -> Option<Instant> {
+        self.at
+    }
+
+    pub fn is_never(&self) -> bool {
+        self.at.is_none()
+    }
+
+    /// `Duration::MAX` for a deadline that never expires.
+    pub fn remaining(&self) -> Duration {
+        self.at.map_or(Duration::MAX, |at| {
+            at.saturating_duration_since(Instant::now())
+        })
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    /// A deadline `budget` from now, clamped to this deadline.
+    pub fn child(&self, budget: Duration) -> Self {
+        Self::after(budget).min(*self)
+    }
+
+    /// Runs `f` with a child deadline of `budget`, reporting `stage` if it overran.
+    pub fn stage<T, F>(&self, stage: &str, budget: Duration, f: F) -> Result<T, TimeoutExceeded>
+    where
+        F: FnOnce(Deadline) -> T,
+    {
+        run(Some(stage), self.child(budget), f)
+    }
+}
+
+/// Earlier deadlines order first; `never` orders after every instant.
+impl Ord for Deadline {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (self.at, other.at) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        }
+    }
+}
+
+impl PartialOrd for Deadline {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Runs `f` with a deadline of `duration` from now.
+///
+/// Host calls cannot be preempted, so `f` is expected to check the deadline it
+/// is given; the overrun is detected and reported once `f` returns.
+pub fn with_timeout<T, F>(f: F, duration: Duration) -> Result<T, TimeoutExceeded>
+where
+    F: FnOnce(Deadline) -> T,
+{
+    run(None, Deadline::after(duration), f)
+}
+
+fn run<T, F>(stage: Option<&str>, deadline: Deadline, f: F) -> Result<T, TimeoutExceeded>
+where
+    F: FnOnce(Deadline) -> T,
+{
+    let start = Instant::now();
+    let budget = deadline.remaining();
+    let rs = f(deadline);
+    let elapsed = start.elapsed();
+    if elapsed > budget {
+        return Err(TimeoutExceeded {
+            stage: stage.map(str::to_string),
+            budget,
+            elapsed,
+        });
+    }
+    Ok(rs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huge_durations_never_expire() {
+        let deadline = Deadline::after(Duration::MAX);
+        assert!(deadline.is_never());
+        assert!(!deadline.is_expired());
+        assert_eq!(deadline.remaining(), Duration::MAX);
+        assert_eq!(deadline.instant(), None);
+    }
+
+    #[test]
+    fn child_is_clamped_to_parent() {
+        let parent = Deadline::after(Duration::from_secs(1));
+        assert_eq!(parent.child(Duration::MAX), parent);
+        assert!(parent.child(Duration::from_millis(1)) < parent);
+        let never = Deadline::never();
+        assert_eq!(never.child(Duration::MAX), never);
+        assert!(never.child(Duration::from_secs(1)) < never);
+    }
+
+    #[test]
+    fn zero_duration_is_expired() {
+        assert!(Deadline::after(Duration::ZERO).is_expired());
+    }
+
+    #[test]
+    fn with_timeout_reports_overrun() {
+        assert_eq!(with_timeout(|_| 7, Duration::MAX), Ok(7));
+        let err = with_timeout(
+            |_| std::thread::sleep(Duration::from_millis(5)),
+            Duration::from_millis(1),
+        )
+        .unwrap_err();
+        assert!(err.elapsed > err.budget);
+        assert_eq!(err.stage, None);
+    }
+}
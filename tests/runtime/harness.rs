@@ -0,0 +1,104 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+pub struct RunOutput {
+    pub status: i32,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+fn wasm_target() -> String {
+    std::env::var("BLS_WASM_TARGET").unwrap_or_else(|_| "wasm32-wasi".to_string())
+}
+
+fn runtime_path() -> PathBuf {
+    if let Ok(path) = std::env::var("BLS_RUNTIME") {
+        return PathBuf::from(path);
+    }
+    let home = std::env::var("HOME").expect("HOME is not set");
+    Path::new(&home).join(".bls/runtime/bls-runtime")
+}
+
+/// Whether the standard library for `target` is installed in the active toolchain.
+fn target_installed(target: &str) -> bool {
+    let sysroot = Command::new("rustc")
+        .args(["--print", "sysroot"])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
+    matches!(sysroot, Ok(root) if Path::new(&root).join("lib/rustlib").join(target).exists())
+}
+
+/// Builds the example for the wasm target and returns the path of the module.
+pub fn build_example(name: &str) -> PathBuf {
+    let target = wasm_target();
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let status = Command::new(env!("CARGO"))
+        .current_dir(manifest_dir)
+        .args(["build", "--release", "--target", &target, "--example", name])
+        .status()
+        .expect("failed to run cargo");
+    assert!(status.success(), "building example {} failed", name);
+    Path::new(manifest_dir)
+        .join("target")
+        .join(&target)
+        .join("release/examples")
+        .join(format!("{}.wasm", name))
+}
+
+/// Builds and runs the example with the local blockless runtime. `None`, after
+/// printing why, when the wasm target or the runtime is not installed, so the
+/// suite is skipped rather than failed on machines that cannot run it.
+pub fn run_example(name: &str, stdin: &str, permissions: &[&str]) -> Option<RunOutput> {
+    let target = wasm_target();
+    if !target_installed(&target) {
+        eprintln!("skipping {}: rust target {} is not installed", name, target);
+        return None;
+    }
+    let runtime = runtime_path();
+    if !runtime.exists() {
+        eprintln!(
+            "skipping {}: blockless runtime not found at {}, set BLS_RUNTIME",
+            name,
+            runtime.display()
+        );
+        return None;
+    }
+    let wasm = build_example(name);
+    let mut cmd = Command::new(runtime);
+    cmd.arg(&wasm);
+    for permission in permissions {
+        cmd.args(["--permission", permission]);
+    }
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start blockless runtime");
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(stdin.as_bytes())
+        .expect("failed to write stdin");
+    let output = child.wait_with_output().expect("runtime did not finish");
+    Some(RunOutput {
+        status: output.status.code().unwrap_or(-1),
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    })
+}
+
+pub fn assert_stdout_contains(output: &RunOutput, needle: &str) {
+    assert!(
+        output.stdout.contains(needle),
+        "expected stdout to contain {:?}\nstatus: {}\nstdout:\n{}\nstderr:\n{}",
+        needle,
+        output.status,
+        output.stdout,
+        output.stderr
+    );
+}
@@ -0,0 +1,43 @@
+//! Runs the examples against a locally installed blockless runtime.
+//!
+//! ```sh
+//! cargo test --features runtime-tests --test runtime
+//! ```
+//!
+//! `BLS_RUNTIME` overrides the runtime binary (default `~/.bls/runtime/bls-runtime`)
+//! and `BLS_WASM_TARGET` the wasm target (default `wasm32-wasi`). Tests are
+//! skipped when either is missing.
+
+mod harness;
+
+use harness::*;
+
+#[test]
+fn httpbin() {
+    let Some(output) = run_example("httpbin", "", &["http://httpbin.org/anything"]) else {
+        return;
+    };
+    assert_stdout_contains(&output, "X-Test = 123");
+}
+
+#[test]
+fn coingecko_oracle() {
+    let Some(output) = run_example(
+        "coingecko_oracle",
+        "bitcoin",
+        &["https://api.coingecko.com/"],
+    ) else {
+        return;
+    };
+    assert_stdout_contains(&output, r#""id":"bitcoin""#);
+    assert_stdout_contains(&output, r#""currency":"usd""#);
+}
+
+#[test]
+fn llm() {
+    let Some(output) = run_example("llm", "", &[]) else {
+        return;
+    };
+    assert_stdout_contains(&output, "LLM Response:");
+    assert_stdout_contains(&output, "LLM Response SmolLM:");
+}